@@ -0,0 +1,152 @@
+/*
+ * C interface to erikrub_chess_lib, built with `cargo build --release --features ffi`.
+ * Link against liberikrub_chess_lib.a or liberikrub_chess_lib.so.
+ *
+ * Squares are numbered 0 = a8, 1 = b8, ... 63 = h1.
+ */
+
+#ifndef ERIKRUB_CHESS_H
+#define ERIKRUB_CHESS_H
+
+/* Generated with cbindgen from src/ffi.rs, do not edit by hand. */
+
+#include <stddef.h>
+#include <stdint.h>
+
+/**
+ * Bad arguments, such as a null pointer.
+ */
+#define CHESS_ERROR -1
+
+#define CHESS_ILLEGAL_MOVE -2
+
+/**
+ * States, the return values that are >= 0.
+ */
+#define CHESS_IN_PROGRESS 0
+
+#define CHESS_SET_PROMOTION 1
+
+#define CHESS_CHECK 2
+
+#define CHESS_GAME_OVER 3
+
+/**
+ * Pieces for `chess_game_promote`.
+ */
+#define CHESS_QUEEN 1
+
+#define CHESS_BISHOP 2
+
+#define CHESS_KNIGHT 3
+
+#define CHESS_ROOK 4
+
+/**
+ * Colors returned by `chess_game_turn`.
+ */
+#define CHESS_WHITE 0
+
+#define CHESS_BLACK 1
+
+typedef struct ChessGame ChessGame;
+
+typedef struct {
+  uint8_t from;
+  uint8_t to;
+} ChessMove;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * New game in the standard start position.
+ */
+ChessGame *chess_game_new(void);
+
+/**
+ * New game from a FEN string, null if the string is not valid.
+ *
+ * # Safety
+ * `fen` must be null or a NUL-terminated string.
+ */
+ChessGame *chess_game_from_fen(const char *fen);
+
+/**
+ * # Safety
+ * `game` must be null or a handle that has not been freed yet.
+ */
+void chess_game_free(ChessGame *game);
+
+/**
+ * Writes up to `capacity` moves of the side to move into `moves` and returns
+ * how many moves there are in total, which can be more than `capacity`.
+ *
+ * # Safety
+ * `game` must be a live handle and `moves` must point to at least `capacity`
+ * writable `ChessMove`s (it may be null when `capacity` is 0).
+ */
+int32_t chess_game_legal_moves(const ChessGame *game, ChessMove *moves, size_t capacity);
+
+/**
+ * Makes a move and returns the new state, or `CHESS_ILLEGAL_MOVE` if the
+ * move is not allowed.
+ *
+ * # Safety
+ * `game` must be null or a live handle.
+ */
+int32_t chess_game_make_move(ChessGame *game, uint8_t from, uint8_t to);
+
+/**
+ * Chooses the piece for a pending promotion (one of the `CHESS_QUEEN`,
+ * `CHESS_BISHOP`, `CHESS_KNIGHT`, `CHESS_ROOK` values) and returns the new
+ * state. Gives `CHESS_ILLEGAL_MOVE` if no promotion is pending.
+ *
+ * # Safety
+ * `game` must be null or a live handle.
+ */
+int32_t chess_game_promote(ChessGame *game, int32_t piece);
+
+/**
+ * The state of the game, one of the `CHESS_IN_PROGRESS` to
+ * `CHESS_GAME_OVER` values.
+ *
+ * # Safety
+ * `game` must be null or a live handle.
+ */
+int32_t chess_game_state(const ChessGame *game);
+
+/**
+ * `CHESS_WHITE` or `CHESS_BLACK`, the side to move.
+ *
+ * # Safety
+ * `game` must be null or a live handle.
+ */
+int32_t chess_game_turn(const ChessGame *game);
+
+/**
+ * Takes back the last move and returns the new state, or
+ * `CHESS_ILLEGAL_MOVE` if no move has been made.
+ *
+ * # Safety
+ * `game` must be null or a live handle.
+ */
+int32_t chess_game_undo(ChessGame *game);
+
+/**
+ * Writes the position as a NUL-terminated FEN string into `buffer` and
+ * returns its length without the NUL. Like `snprintf`, nothing is written
+ * if `capacity` is too small, so the caller can retry with a bigger buffer.
+ *
+ * # Safety
+ * `game` must be a live handle and `buffer` must point to at least
+ * `capacity` writable bytes (it may be null when `capacity` is 0).
+ */
+int32_t chess_game_fen(const ChessGame *game, char *buffer, size_t capacity);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* ERIKRUB_CHESS_H */
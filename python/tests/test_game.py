@@ -0,0 +1,67 @@
+# Run with: maturin develop && pytest python/tests
+import pytest
+
+import erikrub_chess_lib as chess
+
+
+def test_play_and_undo():
+    game = chess.Game()
+    assert game.turn == chess.PieceColor.White
+    assert game.moves_from("e2") == ["e3", "e4"]
+    assert len(list(game.legal_moves())) == 20
+
+    assert game.make_move("e2", "e4") == chess.GameState.InProgress
+    assert game.push_san("e5") == chess.GameState.InProgress
+    assert game.push_uci("g1f3") == chess.GameState.InProgress
+    assert [str(move) for move in game.history] == ["e2e4", "e7e5", "g1f3"]
+
+    assert game.undo() == chess.Move.from_uci("g1f3")
+    assert game.fen == "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2"
+
+
+def test_errors_are_exceptions():
+    game = chess.Game()
+    with pytest.raises(chess.IllegalMoveError):
+        game.make_move("e2", "e5")
+    with pytest.raises(chess.IllegalMoveError):
+        game.push_san("Ke2")
+    with pytest.raises(ValueError):
+        game.moves_from("z9")
+    with pytest.raises(ValueError):
+        chess.Game.from_fen("not a fen")
+    with pytest.raises(IndexError):
+        game.undo()
+
+
+def test_failed_push_changes_nothing():
+    game = chess.Game()
+    with pytest.raises(chess.IllegalMoveError):
+        game.push_uci("e2e4q")
+    assert game.fen == chess.Game().fen
+
+    game = chess.Game.from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1")
+    with pytest.raises(chess.IllegalMoveError):
+        game.push_uci("b7b8k")
+    assert game.fen == "4k3/1P6/8/8/8/8/8/4K3 w - - 0 1"
+    assert game.push_uci("b7b8q") == chess.GameState.Check
+
+
+def test_san_and_pgn():
+    game = chess.Game()
+    move = game.parse_san("Nf3")
+    assert move.from_square == "g1"
+    assert move.to_square == "f3"
+    assert game.san(move) == "Nf3"
+    game.push(move)
+
+    loaded = chess.Game.from_pgn(game.pgn())
+    assert loaded.fen == game.fen
+
+
+def test_promotion():
+    game = chess.Game.from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1")
+    assert game.make_move("b7", "b8") == chess.GameState.SetPromotion
+    assert game.set_promotion(chess.PieceType.Queen) == chess.GameState.Check
+    assert game.history[-1].promotion == chess.PieceType.Queen
+    with pytest.raises(chess.IllegalMoveError):
+        game.set_promotion(chess.PieceType.Queen)
@@ -0,0 +1,125 @@
+use crate::fen::{find_king, opposite};
+use crate::{square_index, square_name, Game, PieceColor, PieceType};
+
+/// A piece that can not leave the line between its king and an enemy
+/// rook, bishop or queen without exposing the king.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pin {
+    pub square: String,
+    pub pinner: String,
+    /// The squares from the king to the pinner, including the pinner. The
+    /// pinned piece may only move along these.
+    pub ray: Vec<String>,
+}
+
+impl Game {
+    /// The squares of the pieces of `color` that attack `square`, sorted.
+    /// None if `square` is not a square name.
+    pub fn attackers_of(&self, square: &str, color: PieceColor) -> Option<Vec<String>> {
+        let pos = square_index(square)?;
+        let mut attackers = self.attackers(&self.gameboard, pos, color);
+        attackers.sort();
+        Some(attackers.into_iter().map(square_name).collect())
+    }
+
+    /// Tells if a piece of color `by` attacks `square`. None if `square` is
+    /// not a square name.
+    pub fn is_square_attacked(&self, square: &str, by: PieceColor) -> Option<bool> {
+        let pos = square_index(square)?;
+        Some(self.square_attacked(&self.gameboard, pos, by))
+    }
+
+    /// Every square a piece of `color` attacks, including squares with
+    /// pieces of its own on them.
+    pub fn attacked_squares(&self, color: PieceColor) -> Vec<String> {
+        (0..64)
+            .filter(|pos| self.square_attacked(&self.gameboard, *pos, color))
+            .map(square_name)
+            .collect()
+    }
+
+    /// The squares of the pieces giving check to the side to move.
+    pub fn checkers(&self) -> Vec<String> {
+        match find_king(&self.gameboard, self.turn) {
+            Some(king) => self.attackers_of(&square_name(king), opposite(self.turn)).unwrap(),
+            None => Vec::new(),
+        }
+    }
+
+    /// The pieces of `color` pinned to their king.
+    pub fn pinned_pieces(&self, color: PieceColor) -> Vec<Pin> {
+        let mut pins: Vec<Pin> = Vec::new();
+        let king = match find_king(&self.gameboard, color) {
+            Some(king) => king,
+            None => return pins,
+        };
+        let distances = self.distances.get(&king).unwrap();
+        for (direction, distance) in distances.iter().enumerate() {
+            let mut pinned: Option<usize> = None;
+            let mut ray: Vec<usize> = Vec::new();
+            let mut pos = king;
+            for _ in 0..*distance {
+                pos = (pos as i16 + self.movements[direction]) as usize;
+                ray.push(pos);
+                let piece = match &self.gameboard[pos] {
+                    Some(piece) => piece,
+                    None => continue,
+                };
+                if piece.piececolor == color {
+                    if pinned.is_some() {
+                        break;
+                    }
+                    pinned = Some(pos);
+                    continue;
+                }
+                let slides = match piece.piecetype {
+                    PieceType::Queen => true,
+                    PieceType::Rook => direction.is_multiple_of(2),
+                    PieceType::Bishop => direction % 2 == 1,
+                    _ => false,
+                };
+                if let (Some(square), true) = (pinned, slides) {
+                    pins.push(Pin {
+                        square: square_name(square),
+                        pinner: square_name(pos),
+                        ray: ray.iter().map(|pos| square_name(*pos)).collect(),
+                    });
+                }
+                break;
+            }
+        }
+        pins
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Game, PieceColor};
+
+    #[test]
+    fn attacks() {
+        let game = Game::new();
+        assert_eq!(game.attackers_of("f3", PieceColor::White), Some(vec!["e2".to_string(), "g2".to_string(), "g1".to_string()]));
+        assert_eq!(game.is_square_attacked("e4", PieceColor::White), Some(false));
+        assert_eq!(game.is_square_attacked("e9", PieceColor::White), None);
+        // the second and third ranks, and every piece but the rooks
+        assert_eq!(game.attacked_squares(PieceColor::White).len(), 22);
+        assert!(game.checkers().is_empty());
+
+        let game = Game::from_fen("4k3/8/8/8/8/5n2/8/r3K2R w - - 0 1").unwrap();
+        assert_eq!(game.checkers(), vec!["f3".to_string(), "a1".to_string()]);
+    }
+
+    #[test]
+    fn pins() {
+        let game = Game::from_fen("4k3/4r3/8/b7/8/2N5/4B3/4K3 w - - 0 1").unwrap();
+        let pins = game.pinned_pieces(PieceColor::White);
+        assert_eq!(pins.len(), 2);
+        assert_eq!(pins[0].square, "e2");
+        assert_eq!(pins[0].pinner, "e7");
+        assert_eq!(pins[0].ray, vec!["e2", "e3", "e4", "e5", "e6", "e7"]);
+        assert_eq!(pins[1].square, "c3");
+        assert_eq!(pins[1].pinner, "a5");
+        assert!(game.pinned_pieces(PieceColor::Black).is_empty());
+    }
+}
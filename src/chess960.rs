@@ -0,0 +1,183 @@
+use crate::{Game, Piece, PieceColor, PieceType};
+
+impl Game {
+    /// Chess960 game from start position number `id`, 0 to 959 in the
+    /// Scharnagl numbering used by most programs. 518 is the standard start
+    /// position. None if `id` is out of range.
+    ///
+    /// Castling follows the Chess960 rules: the king and rook end up on the
+    /// same squares as in standard chess, and the move is made by moving the
+    /// king onto its own rook, e.g. `make_move("b1", "a1")`.
+    pub fn new_chess960(id: u16) -> Option<Game> {
+        let backrow = chess960_backrow(id)?;
+
+        let mut board: Vec<Option<Piece>> = Vec::new();
+        for piecetype in backrow.iter() {
+            board.push(Some(Piece::new(*piecetype, PieceColor::Black)));
+        }
+        for _x in 0..8 {
+            board.push(Some(Piece::new(PieceType::Pawn, PieceColor::Black)));
+        }
+        for _x in 0..32 {
+            board.push(None);
+        }
+        for _x in 0..8 {
+            board.push(Some(Piece::new(PieceType::Pawn, PieceColor::White)));
+        }
+        for piecetype in backrow.iter() {
+            board.push(Some(Piece::new(*piecetype, PieceColor::White)));
+        }
+
+        let mut game = Game::with_board(board, PieceColor::White);
+        let mut rooks: Vec<usize> = Vec::new();
+        for (file, piecetype) in backrow.iter().enumerate().rev() {
+            if *piecetype == PieceType::Rook {
+                rooks.push(56 + file);
+            }
+        }
+        game.castling = rooks.iter().copied().chain(rooks.iter().map(|rook| rook - 56)).collect();
+        game.chess960 = true;
+        game.start_fen = game.to_fen();
+
+        game.firstload();
+
+        Some(game)
+    }
+
+    /// Chess960 game from a random start position. `rng` is called for random
+    /// numbers, e.g. `Game::new_chess960_random(|| rng.next_u32())` with the
+    /// `rand` crate, so tests can pass a fixed sequence.
+    pub fn new_chess960_random(mut rng: impl FnMut() -> u32) -> Game {
+        // Drop the top of the range so every position is equally likely.
+        let limit = u32::MAX - u32::MAX % 960;
+        loop {
+            let number = rng();
+            if number < limit {
+                return Game::new_chess960((number % 960) as u16).unwrap();
+            }
+        }
+    }
+
+    /// Tells if castling follows the Chess960 rules.
+    pub fn is_chess960(&self) -> bool {
+        self.chess960
+    }
+}
+
+/// Back row of Chess960 position `id`, from the a-file to the h-file.
+fn chess960_backrow(id: u16) -> Option<[PieceType; 8]> {
+    if id >= 960 {
+        return None;
+    }
+    let mut backrow: [Option<PieceType>; 8] = [None; 8];
+    let mut n = id as usize;
+
+    // Bishops on light (b, d, f, h) and dark (a, c, e, g) squares.
+    backrow[n % 4 * 2 + 1] = Some(PieceType::Bishop);
+    n /= 4;
+    backrow[n % 4 * 2] = Some(PieceType::Bishop);
+    n /= 4;
+
+    place_on_empty(&mut backrow, n % 6, PieceType::Queen);
+    n /= 6;
+
+    let knights = [(0, 1), (0, 2), (0, 3), (0, 4), (1, 2), (1, 3), (1, 4), (2, 3), (2, 4), (3, 4)];
+    let (first, second) = knights[n];
+    // The second knight is placed first so the first index still counts
+    // the same empty squares.
+    place_on_empty(&mut backrow, second, PieceType::Knight);
+    place_on_empty(&mut backrow, first, PieceType::Knight);
+
+    // Rook, king and rook on what is left.
+    place_on_empty(&mut backrow, 0, PieceType::Rook);
+    place_on_empty(&mut backrow, 0, PieceType::King);
+    place_on_empty(&mut backrow, 0, PieceType::Rook);
+
+    Some(backrow.map(|piecetype| piecetype.unwrap()))
+}
+
+fn place_on_empty(backrow: &mut [Option<PieceType>; 8], index: usize, piecetype: PieceType) {
+    let file = (0..8).filter(|file| backrow[*file].is_none()).nth(index).unwrap();
+    backrow[file] = Some(piecetype);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Game, GameState};
+    use std::collections::HashSet;
+
+    #[test]
+    fn start_positions() {
+        let game = Game::new_chess960(518).unwrap();
+        assert_eq!(game.to_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(game.to_shredder_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1");
+        assert_eq!(Game::new_chess960(0).unwrap().to_fen(), "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1");
+        assert!(Game::new_chess960(960).is_none());
+
+        let mut positions: HashSet<String> = HashSet::new();
+        for id in 0..960 {
+            let fen = Game::new_chess960(id).unwrap().to_fen();
+            let backrow = &fen[fen.rfind('/').unwrap() + 1..fen.find(' ').unwrap()];
+            let king = backrow.find('K').unwrap();
+            assert!(backrow.find('R').unwrap() < king && backrow.rfind('R').unwrap() > king);
+            let bishops: Vec<usize> = backrow.match_indices('B').map(|(i, _)| i).collect();
+            assert_ne!(bishops[0] % 2, bishops[1] % 2);
+            positions.insert(backrow.to_string());
+        }
+        assert_eq!(positions.len(), 960);
+    }
+
+    #[test]
+    fn random_position_uses_rng() {
+        let mut numbers = vec![u32::MAX, 960 + 518].into_iter();
+        let game = Game::new_chess960_random(|| numbers.next().unwrap());
+        assert_eq!(game.to_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert!(game.is_chess960());
+    }
+
+    #[test]
+    fn castle_by_taking_own_rook() {
+        let mut game = Game::from_fen("1r4kr/8/8/8/8/8/8/1R4KR w HBhb - 0 1").unwrap();
+        assert!(game.is_chess960());
+        assert_eq!(game.to_fen(), "1r4kr/8/8/8/8/8/8/1R4KR w KQkq - 0 1");
+        let moves = game.get_possible_moves("g1").unwrap();
+        assert!(moves.contains(&"h1".to_string()) && moves.contains(&"b1".to_string()));
+
+        // king already on its destination, only the rook moves
+        game.make_move("g1", "h1");
+        assert_eq!(game.to_fen(), "1r4kr/8/8/8/8/8/8/1R3RK1 b kq - 1 1");
+
+        // the white rook on f1 now covers f8
+        let moves = game.get_possible_moves("g8").unwrap();
+        assert!(!moves.contains(&"b8".to_string()) && moves.contains(&"h8".to_string()));
+        game.make_move("g8", "h8");
+        assert_eq!(game.to_fen(), "1r3rk1/8/8/8/8/8/8/1R3RK1 w - - 2 2");
+    }
+
+    #[test]
+    fn castle_queenside_past_rook() {
+        let mut game = Game::from_fen("1r4kr/8/8/8/8/8/8/1R4KR w HBhb - 0 1").unwrap();
+        game.make_move("g1", "b1");
+        assert_eq!(game.to_fen(), "1r4kr/8/8/8/8/8/8/2KR3R b kq - 1 1");
+        assert_eq!(game.get_history()[0].to_string(), "g1b1");
+    }
+
+    #[test]
+    fn rook_already_on_destination() {
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/8/4KR2 w F - 0 1").unwrap();
+        assert_eq!(game.to_fen(), "4k3/8/8/8/8/8/8/4KR2 w K - 0 1");
+        game.make_move("e1", "f1");
+        assert_eq!(game.to_fen(), "4k3/8/8/8/8/8/8/5RK1 b - - 1 1");
+    }
+
+    #[test]
+    fn no_castling_through_attacked_squares() {
+        // the rook on a1 would attack the king on c1 once the b1 rook moves
+        let game = Game::from_fen("k7/8/8/8/8/8/8/rR1K4 w B - 0 1").unwrap();
+        assert!(!game.get_possible_moves("d1").unwrap().contains(&"b1".to_string()));
+
+        let game = Game::from_fen("4k3/8/8/8/8/8/8/1rRK4 w C - 0 1").unwrap();
+        assert!(!game.get_possible_moves("d1").unwrap().contains(&"c1".to_string()));
+        assert_eq!(game.get_game_state(), GameState::InProgress);
+    }
+}
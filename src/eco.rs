@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::Game;
+
+/// A named opening from the Encyclopaedia of Chess Openings.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Opening {
+    pub eco: &'static str,
+    pub name: &'static str,
+}
+
+impl Game {
+    /// The opening the game has reached: the last position in the game that
+    /// is named in the ECO table, however the moves got there. None if no
+    /// position is named, and for variants.
+    ///
+    /// The table only has about a hundred common openings, not every code
+    /// from A00 to E99. Most lines therefore get the name of the broader
+    /// opening they belong to, e.g. a Classical Sicilian (B56) is named
+    /// "Sicilian Defense" (B50), and rare first moves like 1. a3 get None.
+    pub fn opening(&self) -> Option<Opening> {
+        if self.variant.name() != "Standard" {
+            return None;
+        }
+        let table = table();
+        let mut game = self.start_position();
+        let mut opening = table.get(&game.polyglot_key()).copied();
+        for _move in self.history.iter() {
+            game.replay(_move);
+            if let Some(named) = table.get(&game.polyglot_key()) {
+                opening = Some(*named);
+            }
+        }
+        opening
+    }
+}
+
+/// The openings by the Polyglot key of their position.
+fn table() -> &'static HashMap<u64, Opening> {
+    static TABLE: OnceLock<HashMap<u64, Opening>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut table = HashMap::new();
+        for (eco, name, moves) in OPENINGS {
+            let mut game = Game::new();
+            for san in moves.split_whitespace() {
+                game.make_san_move(san);
+            }
+            table.insert(game.polyglot_key(), Opening { eco, name });
+        }
+        table
+    })
+}
+
+const OPENINGS: &[(&str, &str, &str)] = &[
+    ("A00", "Polish Opening", "b4"),
+    ("A00", "Grob Opening", "g4"),
+    ("A00", "Van 't Kruijs Opening", "e3"),
+    ("A01", "Nimzo-Larsen Attack", "b3"),
+    ("A02", "Bird Opening", "f4"),
+    ("A04", "Zukertort Opening", "Nf3"),
+    ("A09", "Réti Opening", "Nf3 d5 c4"),
+    ("A10", "English Opening", "c4"),
+    ("A15", "English Opening: Anglo-Indian Defense", "c4 Nf6"),
+    ("A20", "English Opening: King's English Variation", "c4 e5"),
+    ("A30", "English Opening: Symmetrical Variation", "c4 c5"),
+    ("A40", "Queen's Pawn Game", "d4"),
+    ("A43", "Benoni Defense: Old Benoni", "d4 c5"),
+    ("A45", "Indian Defense", "d4 Nf6"),
+    ("A51", "Indian Defense: Budapest Defense", "d4 Nf6 c4 e5"),
+    ("A56", "Benoni Defense", "d4 Nf6 c4 c5"),
+    ("A57", "Benko Gambit", "d4 Nf6 c4 c5 d5 b5"),
+    ("A60", "Benoni Defense: Modern Variation", "d4 Nf6 c4 c5 d5 e6"),
+    ("A80", "Dutch Defense", "d4 f5"),
+    ("B00", "King's Pawn Game", "e4"),
+    ("B00", "Nimzowitsch Defense", "e4 Nc6"),
+    ("B01", "Scandinavian Defense", "e4 d5"),
+    ("B01", "Scandinavian Defense: Mieses-Kotroc Variation", "e4 d5 exd5 Qxd5"),
+    ("B02", "Alekhine Defense", "e4 Nf6"),
+    ("B06", "Modern Defense", "e4 g6"),
+    ("B07", "Pirc Defense", "e4 d6 d4 Nf6 Nc3 g6"),
+    ("B10", "Caro-Kann Defense", "e4 c6"),
+    ("B12", "Caro-Kann Defense: Advance Variation", "e4 c6 d4 d5 e5"),
+    ("B13", "Caro-Kann Defense: Exchange Variation", "e4 c6 d4 d5 exd5 cxd5"),
+    ("B18", "Caro-Kann Defense: Classical Variation", "e4 c6 d4 d5 Nc3 dxe4 Nxe4 Bf5"),
+    ("B20", "Sicilian Defense", "e4 c5"),
+    ("B21", "Sicilian Defense: Smith-Morra Gambit", "e4 c5 d4 cxd4 c3"),
+    ("B22", "Sicilian Defense: Alapin Variation", "e4 c5 c3"),
+    ("B23", "Sicilian Defense: Closed", "e4 c5 Nc3"),
+    ("B27", "Sicilian Defense", "e4 c5 Nf3"),
+    ("B30", "Sicilian Defense: Old Sicilian", "e4 c5 Nf3 Nc6"),
+    ("B33", "Sicilian Defense: Lasker-Pelikan Variation", "e4 c5 Nf3 Nc6 d4 cxd4 Nxd4 Nf6 Nc3 e5"),
+    ("B40", "Sicilian Defense: French Variation", "e4 c5 Nf3 e6"),
+    ("B50", "Sicilian Defense", "e4 c5 Nf3 d6"),
+    ("B70", "Sicilian Defense: Dragon Variation", "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 g6"),
+    ("B80", "Sicilian Defense: Scheveningen Variation", "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 e6"),
+    ("B90", "Sicilian Defense: Najdorf Variation", "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 a6"),
+    ("C00", "French Defense", "e4 e6"),
+    ("C01", "French Defense: Exchange Variation", "e4 e6 d4 d5 exd5"),
+    ("C02", "French Defense: Advance Variation", "e4 e6 d4 d5 e5"),
+    ("C03", "French Defense: Tarrasch Variation", "e4 e6 d4 d5 Nd2"),
+    ("C10", "French Defense: Paulsen Variation", "e4 e6 d4 d5 Nc3"),
+    ("C11", "French Defense: Classical Variation", "e4 e6 d4 d5 Nc3 Nf6"),
+    ("C15", "French Defense: Winawer Variation", "e4 e6 d4 d5 Nc3 Bb4"),
+    ("C20", "King's Pawn Game", "e4 e5"),
+    ("C21", "Center Game", "e4 e5 d4 exd4"),
+    ("C23", "Bishop's Opening", "e4 e5 Bc4"),
+    ("C25", "Vienna Game", "e4 e5 Nc3"),
+    ("C30", "King's Gambit", "e4 e5 f4"),
+    ("C33", "King's Gambit Accepted", "e4 e5 f4 exf4"),
+    ("C40", "King's Knight Opening", "e4 e5 Nf3"),
+    ("C41", "Philidor Defense", "e4 e5 Nf3 d6"),
+    ("C42", "Petrov's Defense", "e4 e5 Nf3 Nf6"),
+    ("C44", "King's Knight Opening: Normal Variation", "e4 e5 Nf3 Nc6"),
+    ("C44", "Ponziani Opening", "e4 e5 Nf3 Nc6 c3"),
+    ("C44", "Scotch Game", "e4 e5 Nf3 Nc6 d4"),
+    ("C45", "Scotch Game", "e4 e5 Nf3 Nc6 d4 exd4 Nxd4"),
+    ("C46", "Three Knights Opening", "e4 e5 Nf3 Nc6 Nc3"),
+    ("C47", "Four Knights Game", "e4 e5 Nf3 Nc6 Nc3 Nf6"),
+    ("C50", "Italian Game", "e4 e5 Nf3 Nc6 Bc4"),
+    ("C50", "Italian Game: Giuoco Piano", "e4 e5 Nf3 Nc6 Bc4 Bc5"),
+    ("C51", "Italian Game: Evans Gambit", "e4 e5 Nf3 Nc6 Bc4 Bc5 b4"),
+    ("C55", "Italian Game: Two Knights Defense", "e4 e5 Nf3 Nc6 Bc4 Nf6"),
+    ("C57", "Italian Game: Two Knights Defense, Knight Attack", "e4 e5 Nf3 Nc6 Bc4 Nf6 Ng5"),
+    ("C60", "Ruy Lopez", "e4 e5 Nf3 Nc6 Bb5"),
+    ("C65", "Ruy Lopez: Berlin Defense", "e4 e5 Nf3 Nc6 Bb5 Nf6"),
+    ("C68", "Ruy Lopez: Exchange Variation", "e4 e5 Nf3 Nc6 Bb5 a6 Bxc6"),
+    ("C70", "Ruy Lopez: Morphy Defense", "e4 e5 Nf3 Nc6 Bb5 a6 Ba4"),
+    ("C84", "Ruy Lopez: Closed", "e4 e5 Nf3 Nc6 Bb5 a6 Ba4 Nf6 O-O Be7"),
+    ("D00", "Queen's Pawn Game", "d4 d5"),
+    ("D00", "Blackmar-Diemer Gambit", "d4 d5 e4"),
+    ("D00", "Queen's Pawn Game: Accelerated London System", "d4 d5 Bf4"),
+    ("D02", "Queen's Pawn Game: Zukertort Variation", "d4 d5 Nf3"),
+    ("D06", "Queen's Gambit", "d4 d5 c4"),
+    ("D07", "Queen's Gambit Declined: Chigorin Defense", "d4 d5 c4 Nc6"),
+    ("D08", "Queen's Gambit Declined: Albin Countergambit", "d4 d5 c4 e5"),
+    ("D10", "Slav Defense", "d4 d5 c4 c6"),
+    ("D20", "Queen's Gambit Accepted", "d4 d5 c4 dxc4"),
+    ("D30", "Queen's Gambit Declined", "d4 d5 c4 e6"),
+    ("D43", "Semi-Slav Defense", "d4 d5 c4 e6 Nc3 Nf6 Nf3 c6"),
+    ("D80", "Grünfeld Defense", "d4 Nf6 c4 g6 Nc3 d5"),
+    ("E00", "Catalan Opening", "d4 Nf6 c4 e6 g3"),
+    ("E12", "Queen's Indian Defense", "d4 Nf6 c4 e6 Nf3 b6"),
+    ("E20", "Nimzo-Indian Defense", "d4 Nf6 c4 e6 Nc3 Bb4"),
+    ("E60", "King's Indian Defense", "d4 Nf6 c4 g6"),
+    ("E61", "King's Indian Defense", "d4 Nf6 c4 g6 Nc3 Bg7"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::{Opening, OPENINGS};
+    use crate::{variant, Game};
+
+    fn play(moves: &str) -> Game {
+        let mut game = Game::new();
+        for san in moves.split_whitespace() {
+            game.make_san_move(san).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn every_line_is_legal() {
+        for (_, _, moves) in OPENINGS {
+            play(moves);
+        }
+        // no two lines reach the same position
+        assert_eq!(super::table().len(), OPENINGS.len());
+    }
+
+    #[test]
+    fn opening() {
+        assert_eq!(Game::new().opening(), None);
+        let italian = Some(Opening { eco: "C50", name: "Italian Game" });
+        assert_eq!(play("e4 e5 Nf3 Nc6 Bc4").opening(), italian);
+        // the last named position counts once the game leaves the table
+        assert_eq!(play("e4 e5 Nf3 Nc6 Bc4 h6 a3").opening(), italian);
+        // transpositions
+        assert_eq!(play("Nf3 d5 d4").opening().unwrap().eco, "D02");
+        assert_eq!(play("c4 e6 d4 Nf6 Nc3 Bb4").opening().unwrap().name, "Nimzo-Indian Defense");
+        assert_eq!(Game::new_variant(variant::Horde).opening(), None);
+        // lines the table does not have get the broader opening, or nothing
+        let classical = play("e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 Nc6").opening().unwrap();
+        assert_eq!((classical.eco, classical.name), ("B50", "Sicilian Defense"));
+        assert_eq!(play("a3").opening(), None);
+    }
+}
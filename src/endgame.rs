@@ -0,0 +1,619 @@
+//! Endgame tables for KQK, KRK, KPK and KBNK, made by retrograde analysis
+//! the first time they are needed.
+//!
+//! The tables work on their own compact positions: the side with the extra
+//! pieces is white and moves up the board, squares go from a1 = 0 to h8 = 63,
+//! and positions without pawns are turned so the lone king is in the
+//! a1-d1-d4 triangle. Each entry is the number of plies to mate.
+
+use std::cmp::{self, Ordering};
+use std::sync::OnceLock;
+
+use crate::{Game, Move, Piece, PieceColor, PieceType};
+
+/// The result of a position with perfect play, for the side to move.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum EndgameResult {
+    /// The side to move mates in this many moves.
+    Win(u32),
+    /// The side to move is mated in this many moves, 0 if it already is.
+    Loss(u32),
+    Draw,
+}
+
+impl Game {
+    /// The result of the position with perfect play, if it is one of the
+    /// endgames the crate has tables for (king and queen, rook, pawn or
+    /// bishop and knight against a lone king), or a draw by bare kings or a
+    /// single minor piece. None for other positions, positions with castling
+    /// rights, and variants.
+    pub fn endgame_result(&self) -> Option<EndgameResult> {
+        if self.variant.name() != "Standard" || !self.castling.is_empty() {
+            return None;
+        }
+        probe(&self.gameboard, self.turn)
+    }
+
+    /// A move that keeps the best result: the fastest mate when winning,
+    /// the slowest when losing, and a drawing move otherwise. None if
+    /// `endgame_result` is None or there are no moves.
+    pub fn endgame_move(&self) -> Option<Move> {
+        self.endgame_result()?;
+        let mut best: Option<(Move, i64)> = None;
+        for _move in self.get_legal_moves() {
+            let is_promotion = matches!(&self.gameboard[_move.from], Some(piece) if piece.piecetype == PieceType::Pawn)
+                && (_move.to / 8 == 0 || _move.to / 8 == 7);
+            let promotions = if is_promotion {
+                vec![Some(PieceType::Queen), Some(PieceType::Rook), Some(PieceType::Bishop), Some(PieceType::Knight)]
+            } else {
+                vec![None]
+            };
+            for promotion in promotions {
+                let _move = Move { promotion, .._move };
+                let result = probe(&self.board_after(&_move), crate::fen::opposite(self.turn))?;
+                // higher is better for the side to move
+                let score = match result {
+                    EndgameResult::Loss(moves) => 1000 - moves as i64,
+                    EndgameResult::Draw => 0,
+                    EndgameResult::Win(moves) => moves as i64 - 1000,
+                };
+                if best.is_none_or(|(_, best)| score > best) {
+                    best = Some((_move, score));
+                }
+            }
+        }
+        best.map(|(_move, _)| _move)
+    }
+}
+
+/// Looks up `board` with `turn` to move.
+fn probe(board: &[Option<Piece>], turn: PieceColor) -> Option<EndgameResult> {
+    let mut kings = [None, None];
+    let mut extra: [Vec<(PieceType, usize)>; 2] = [Vec::new(), Vec::new()];
+    for (pos, piece) in board.iter().enumerate() {
+        if let Some(piece) = piece {
+            let color = piece.piececolor as usize;
+            if piece.piecetype == PieceType::King {
+                if kings[color].is_some() {
+                    return None;
+                }
+                kings[color] = Some(pos);
+            } else {
+                extra[color].push((piece.piecetype, pos));
+            }
+        }
+    }
+    let (strong, weak) = match (extra[0].is_empty(), extra[1].is_empty()) {
+        (_, true) => (PieceColor::White, PieceColor::Black),
+        (true, false) => (PieceColor::Black, PieceColor::White),
+        (false, false) => return None,
+    };
+    let pieces = &mut extra[strong as usize];
+    pieces.sort_by_key(|(piecetype, _)| table_order(*piecetype));
+    let types: Vec<PieceType> = pieces.iter().map(|(piecetype, _)| *piecetype).collect();
+    if matches!(types.as_slice(), [] | [PieceType::Bishop] | [PieceType::Knight]) {
+        return Some(EndgameResult::Draw);
+    }
+    let table = table_for(&types)?;
+
+    // the strong side becomes white, moving up the board
+    let square = |pos: usize| match strong {
+        PieceColor::White => ((7 - pos / 8) * 8 + pos % 8) as u8,
+        PieceColor::Black => pos as u8,
+    };
+    let mut position = Position {
+        wk: square(kings[strong as usize]?),
+        bk: square(kings[weak as usize]?),
+        pieces: [NONE, NONE],
+        white_to_move: turn == strong,
+    };
+    for (i, (_, pos)) in pieces.iter().enumerate() {
+        position.pieces[i] = square(*pos);
+    }
+    if !table.valid(&position) {
+        return None;
+    }
+    let plies = table.dtm[table.index(&position)] as u32;
+    Some(if plies >= INVALID as u32 {
+        EndgameResult::Draw
+    } else if position.white_to_move {
+        EndgameResult::Win(plies.div_ceil(2))
+    } else {
+        EndgameResult::Loss(plies / 2)
+    })
+}
+
+fn table_order(piecetype: PieceType) -> u8 {
+    match piecetype {
+        PieceType::Queen => 0,
+        PieceType::Rook => 1,
+        PieceType::Bishop => 2,
+        PieceType::Knight => 3,
+        PieceType::Pawn => 4,
+        PieceType::King => 5,
+    }
+}
+
+/// The table for the strong side's pieces, sorted by `table_order`.
+fn table_for(pieces: &[PieceType]) -> Option<&'static Table> {
+    static KQK: OnceLock<Table> = OnceLock::new();
+    static KRK: OnceLock<Table> = OnceLock::new();
+    static KBNK: OnceLock<Table> = OnceLock::new();
+    static KPK: OnceLock<Table> = OnceLock::new();
+    match pieces {
+        [PieceType::Queen] => Some(KQK.get_or_init(|| Table::generate(&[PieceType::Queen], &[]))),
+        [PieceType::Rook] => Some(KRK.get_or_init(|| Table::generate(&[PieceType::Rook], &[]))),
+        [PieceType::Bishop, PieceType::Knight] => {
+            Some(KBNK.get_or_init(|| Table::generate(&[PieceType::Bishop, PieceType::Knight], &[])))
+        }
+        [PieceType::Pawn] => Some(KPK.get_or_init(|| {
+            let queen = table_for(&[PieceType::Queen]).unwrap();
+            let rook = table_for(&[PieceType::Rook]).unwrap();
+            Table::generate(&[PieceType::Pawn], &[queen, rook])
+        })),
+        _ => None,
+    }
+}
+
+const NONE: u8 = 64;
+const INVALID: u8 = 253;
+const DRAW: u8 = 254;
+const UNKNOWN: u8 = 255;
+
+/// The squares the lone king is turned into when there are no pawns.
+const TRIANGLE: [u8; 10] = [0, 1, 2, 3, 9, 10, 11, 18, 19, 27];
+
+#[derive(Copy, Clone, PartialEq)]
+struct Position {
+    wk: u8,
+    bk: u8,
+    pieces: [u8; 2], // NONE if missing
+    white_to_move: bool,
+}
+
+impl Position {
+    fn occupied(&self, sq: u8) -> bool {
+        self.wk == sq || self.bk == sq || self.pieces.contains(&sq)
+    }
+
+    /// Mirrors and turns the board like `transform`.
+    fn transform(&self, t: u8) -> Position {
+        let square = |sq: u8| if sq == NONE { NONE } else { transform(sq, t) };
+        Position {
+            wk: square(self.wk),
+            bk: square(self.bk),
+            pieces: [square(self.pieces[0]), square(self.pieces[1])],
+            white_to_move: self.white_to_move,
+        }
+    }
+}
+
+struct Table {
+    pieces: Vec<PieceType>,
+    symmetric: bool,
+    dtm: Vec<u8>,
+}
+
+impl Table {
+    /// Works out the table for white with a king and `pieces` against a
+    /// lone king. A pawn promotes into the `promotions` tables.
+    fn generate(pieces: &[PieceType], promotions: &[&Table]) -> Table {
+        let symmetric = !pieces.contains(&PieceType::Pawn);
+        let kings = if symmetric { TRIANGLE.len() } else { 64 };
+        let size = 2 * kings * 64usize.pow(pieces.len() as u32 + 1);
+        let mut table = Table {
+            pieces: pieces.to_vec(),
+            symmetric,
+            dtm: vec![UNKNOWN; size],
+        };
+
+        // buckets[plies] holds positions that are won or lost in that many
+        // plies, and remaining[idx] the lone king's moves not yet known to
+        // lose. dtm is set when a position is put in a bucket, a faster win
+        // found later replaces it.
+        let mut buckets: Vec<Vec<usize>> = vec![Vec::new()];
+        let mut remaining = vec![i8::MAX; size];
+        for (idx, moves) in remaining.iter_mut().enumerate() {
+            let position = table.decode(idx);
+            if !table.valid(&position) || table.index(&position) != idx {
+                table.dtm[idx] = INVALID;
+            } else if !position.white_to_move {
+                if let Some(mut children) = table.black_children(&position) {
+                    // two moves of a king on the diagonal can lead to the
+                    // same child, which is only looked at once
+                    children.sort();
+                    children.dedup();
+                    *moves = children.len() as i8;
+                    if children.is_empty() && table.attacked(&position, position.bk) {
+                        table.dtm[idx] = 0;
+                        buckets[0].push(idx);
+                    } else if children.is_empty() {
+                        table.dtm[idx] = DRAW;
+                    }
+                }
+            } else if let Some(pawn) = position.pieces.iter().position(|sq| *sq != NONE && *sq / 8 == 6) {
+                let to = position.pieces[pawn] + 8;
+                if !position.occupied(to) {
+                    for promoted in promotions {
+                        let child = Position {
+                            pieces: [to, NONE],
+                            white_to_move: false,
+                            ..position
+                        };
+                        let plies = promoted.dtm[promoted.index(&child)] as usize;
+                        if plies < INVALID as usize && plies + 1 < table.dtm[idx] as usize {
+                            table.dtm[idx] = plies as u8 + 1;
+                            if buckets.len() <= plies + 1 {
+                                buckets.resize(plies + 2, Vec::new());
+                            }
+                            buckets[plies + 1].push(idx);
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut plies = 0;
+        while plies < buckets.len() {
+            let bucket = std::mem::take(&mut buckets[plies]);
+            let mut next: Vec<usize> = Vec::new();
+            for idx in bucket {
+                if table.dtm[idx] as usize != plies {
+                    continue;
+                }
+                let position = table.decode(idx);
+                if position.white_to_move {
+                    // the lone king moved here, and lost if all its moves lose. A
+                    // move can be counted twice on a symmetric board, so the
+                    // moves are looked at again once the count runs out.
+                    for parent in table.black_unmoves(&position) {
+                        let parent_idx = table.index(&parent);
+                        if table.dtm[parent_idx] != UNKNOWN {
+                            continue;
+                        }
+                        remaining[parent_idx] = remaining[parent_idx].saturating_sub(1);
+                        if remaining[parent_idx] <= 0 && table.all_children_lost(&parent, plies) {
+                            table.dtm[parent_idx] = plies as u8 + 1;
+                            next.push(parent_idx);
+                        }
+                    }
+                } else {
+                    for parent in table.white_unmoves(&position) {
+                        let parent_idx = table.index(&parent);
+                        if table.dtm[parent_idx] as usize > plies + 1 {
+                            table.dtm[parent_idx] = plies as u8 + 1;
+                            next.push(parent_idx);
+                        }
+                    }
+                }
+            }
+            if !next.is_empty() {
+                if buckets.len() <= plies + 1 {
+                    buckets.push(Vec::new());
+                }
+                buckets[plies + 1].extend(next);
+            }
+            plies += 1;
+        }
+        table
+    }
+
+    fn index(&self, position: &Position) -> usize {
+        if !self.symmetric {
+            return self.raw_index(position);
+        }
+        // mirror the lone king into a1-d4, then below the a1-h8 diagonal
+        let (file, rank) = (position.bk % 8, position.bk / 8);
+        let t = (file > 3) as u8 | ((rank > 3) as u8) << 1;
+        let (file, rank) = (file.min(7 - file), rank.min(7 - rank));
+        match rank.cmp(&file) {
+            Ordering::Less => self.raw_index(&position.transform(t)),
+            Ordering::Greater => self.raw_index(&position.transform(t | 4)),
+            Ordering::Equal => cmp::min(
+                self.raw_index(&position.transform(t)),
+                self.raw_index(&position.transform(t | 4)),
+            ),
+        }
+    }
+
+    fn raw_index(&self, position: &Position) -> usize {
+        let king = if self.symmetric {
+            triangle_index(position.bk).unwrap()
+        } else {
+            position.bk as usize
+        };
+        let kings = if self.symmetric { TRIANGLE.len() } else { 64 };
+        let mut idx = (position.white_to_move as usize) * kings + king;
+        idx = idx * 64 + position.wk as usize;
+        for sq in position.pieces.iter().take(self.pieces.len()) {
+            idx = idx * 64 + *sq as usize;
+        }
+        idx
+    }
+
+    fn decode(&self, mut idx: usize) -> Position {
+        let mut pieces = [NONE, NONE];
+        for i in (0..self.pieces.len()).rev() {
+            pieces[i] = (idx % 64) as u8;
+            idx /= 64;
+        }
+        let wk = (idx % 64) as u8;
+        idx /= 64;
+        let kings = if self.symmetric { TRIANGLE.len() } else { 64 };
+        let bk = if self.symmetric { TRIANGLE[idx % kings] } else { (idx % kings) as u8 };
+        Position {
+            wk,
+            bk,
+            pieces,
+            white_to_move: idx / kings == 1,
+        }
+    }
+
+    /// Tells if the position can happen in a game.
+    fn valid(&self, position: &Position) -> bool {
+        let squares = [position.wk, position.bk, position.pieces[0], position.pieces[1]];
+        let squares = &squares[..self.pieces.len() + 2];
+        for (i, sq) in squares.iter().enumerate() {
+            if squares[..i].contains(sq) {
+                return false;
+            }
+        }
+        for (piecetype, sq) in self.pieces.iter().zip(position.pieces) {
+            if *piecetype == PieceType::Pawn && (sq / 8 == 0 || sq / 8 == 7) {
+                return false;
+            }
+        }
+        if adjacent(position.wk, position.bk) {
+            return false;
+        }
+        !position.white_to_move || !self.attacked(position, position.bk)
+    }
+
+    /// Tells if a white piece attacks `target`.
+    fn attacked(&self, position: &Position, target: u8) -> bool {
+        if adjacent(position.wk, target) {
+            return true;
+        }
+        self.pieces.iter().zip(position.pieces).any(|(piecetype, sq)| {
+            if sq == NONE || sq == target {
+                return false;
+            }
+            let (file, rank) = ((sq % 8) as i8, (sq / 8) as i8);
+            let (df, dr) = ((target % 8) as i8 - file, (target / 8) as i8 - rank);
+            match piecetype {
+                PieceType::Knight => (df.abs() == 1 && dr.abs() == 2) || (df.abs() == 2 && dr.abs() == 1),
+                PieceType::Pawn => dr == 1 && df.abs() == 1,
+                PieceType::King => false,
+                _ => {
+                    let straight = df == 0 || dr == 0;
+                    let diagonal = df.abs() == dr.abs();
+                    let slides = match piecetype {
+                        PieceType::Rook => straight,
+                        PieceType::Bishop => diagonal,
+                        _ => straight || diagonal,
+                    };
+                    if !slides {
+                        return false;
+                    }
+                    let steps = df.abs().max(dr.abs());
+                    (1..steps).all(|step| {
+                        let between = (rank + dr / steps * step) * 8 + file + df / steps * step;
+                        !position.occupied(between as u8)
+                    })
+                }
+            }
+        })
+    }
+
+    /// The positions the lone king can move to, or None if it can take a
+    /// piece, which leaves the table.
+    fn black_children(&self, position: &Position) -> Option<Vec<usize>> {
+        let mut children: Vec<usize> = Vec::new();
+        for to in neighbours(position.bk) {
+            if adjacent(to, position.wk) || to == position.wk {
+                continue;
+            }
+            let mut child = Position {
+                bk: to,
+                white_to_move: true,
+                ..*position
+            };
+            let capture = child.pieces.iter().position(|sq| *sq == to);
+            if let Some(i) = capture {
+                child.pieces[i] = NONE;
+            }
+            if self.attacked(&child, to) {
+                continue;
+            }
+            if capture.is_some() {
+                return None;
+            }
+            children.push(self.index(&child));
+        }
+        Some(children)
+    }
+
+    /// Tells if every move of the lone king in `position` leads to a
+    /// position white wins in at most `plies`.
+    fn all_children_lost(&self, position: &Position, plies: usize) -> bool {
+        match self.black_children(position) {
+            Some(children) => !children.is_empty() && children.iter().all(|child| self.dtm[*child] as usize <= plies),
+            None => false,
+        }
+    }
+
+    /// The positions the lone king can have moved from.
+    fn black_unmoves(&self, position: &Position) -> Vec<Position> {
+        neighbours(position.bk)
+            .filter(|from| !position.occupied(*from))
+            .map(|from| Position {
+                bk: from,
+                white_to_move: false,
+                ..*position
+            })
+            .filter(|parent| self.valid(parent))
+            .collect()
+    }
+
+    /// The positions white can have moved from.
+    fn white_unmoves(&self, position: &Position) -> Vec<Position> {
+        let mut parents: Vec<Position> = Vec::new();
+        for from in neighbours(position.wk) {
+            if !position.occupied(from) {
+                parents.push(Position {
+                    wk: from,
+                    white_to_move: true,
+                    ..*position
+                });
+            }
+        }
+        for (i, piecetype) in self.pieces.iter().enumerate() {
+            let sq = position.pieces[i];
+            let (file, rank) = ((sq % 8) as i8, (sq / 8) as i8);
+            let mut froms: Vec<u8> = Vec::new();
+            match piecetype {
+                PieceType::Pawn => {
+                    if rank >= 2 && !position.occupied(sq - 8) {
+                        froms.push(sq - 8);
+                        if rank == 3 && !position.occupied(sq - 16) {
+                            froms.push(sq - 16);
+                        }
+                    }
+                }
+                PieceType::Knight => {
+                    for (df, dr) in [(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)] {
+                        let (f, r) = (file + df, rank + dr);
+                        if (0..8).contains(&f) && (0..8).contains(&r) && !position.occupied((r * 8 + f) as u8) {
+                            froms.push((r * 8 + f) as u8);
+                        }
+                    }
+                }
+                _ => {
+                    for (df, dr) in [(0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1), (-1, 0), (-1, 1)] {
+                        let diagonal = df != 0 && dr != 0;
+                        if (*piecetype == PieceType::Rook && diagonal) || (*piecetype == PieceType::Bishop && !diagonal) {
+                            continue;
+                        }
+                        let (mut f, mut r) = (file + df, rank + dr);
+                        while (0..8).contains(&f) && (0..8).contains(&r) && !position.occupied((r * 8 + f) as u8) {
+                            froms.push((r * 8 + f) as u8);
+                            f += df;
+                            r += dr;
+                        }
+                    }
+                }
+            }
+            for from in froms {
+                let mut parent = Position {
+                    white_to_move: true,
+                    ..*position
+                };
+                parent.pieces[i] = from;
+                parents.push(parent);
+            }
+        }
+        parents.retain(|parent| self.valid(parent));
+        parents
+    }
+}
+
+fn adjacent(a: u8, b: u8) -> bool {
+    let (df, dr) = ((a % 8) as i8 - (b % 8) as i8, (a / 8) as i8 - (b / 8) as i8);
+    a != b && df.abs() <= 1 && dr.abs() <= 1
+}
+
+fn neighbours(sq: u8) -> impl Iterator<Item = u8> {
+    let (file, rank) = ((sq % 8) as i8, (sq / 8) as i8);
+    [(0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1), (-1, 0), (-1, 1)]
+        .into_iter()
+        .map(move |(df, dr)| (file + df, rank + dr))
+        .filter(|(f, r)| (0..8).contains(f) && (0..8).contains(r))
+        .map(|(f, r)| (r * 8 + f) as u8)
+}
+
+/// Mirrors and turns a square, `t` picks one of the eight ways.
+fn transform(sq: u8, t: u8) -> u8 {
+    let (mut file, mut rank) = (sq % 8, sq / 8);
+    if t & 1 != 0 {
+        file = 7 - file;
+    }
+    if t & 2 != 0 {
+        rank = 7 - rank;
+    }
+    if t & 4 != 0 {
+        (file, rank) = (rank, file);
+    }
+    rank * 8 + file
+}
+
+fn triangle_index(sq: u8) -> Option<usize> {
+    let (file, rank) = (sq % 8, sq / 8);
+    if file > 3 || rank > file {
+        return None;
+    }
+    TRIANGLE.iter().position(|other| *other == sq)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EndgameResult;
+    use crate::{Game, GameState, Move};
+
+    fn result(fen: &str) -> Option<EndgameResult> {
+        Game::from_fen(fen).unwrap().endgame_result()
+    }
+
+    #[test]
+    fn queen_and_rook() {
+        assert_eq!(result("7k/8/6K1/8/8/8/8/1Q6 w - - 0 1"), Some(EndgameResult::Win(1)));
+        assert_eq!(result("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"), Some(EndgameResult::Draw));
+        assert_eq!(result("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1"), Some(EndgameResult::Loss(0)));
+        // the queen is hanging
+        assert_eq!(result("8/8/8/3k4/3Q4/8/8/7K b - - 0 1"), Some(EndgameResult::Draw));
+        // both king moves lead to the same position turned over the diagonal
+        assert_eq!(result("k7/8/2K5/3R4/8/8/8/8 b - - 0 1"), Some(EndgameResult::Loss(2)));
+        // the longest wins take 10 and 16 moves
+        assert_eq!(result("8/8/8/3k4/8/8/8/KQ6 w - - 0 1").map(|r| matches!(r, EndgameResult::Win(1..=10))), Some(true));
+        assert_eq!(result("8/8/8/3k4/8/8/8/KR6 w - - 0 1").map(|r| matches!(r, EndgameResult::Win(1..=16))), Some(true));
+        // colors do not matter
+        assert_eq!(result("1q6/8/8/8/8/6k1/8/7K b - - 0 1"), Some(EndgameResult::Win(1)));
+        assert_eq!(result("4k3/8/8/8/8/8/8/4K3 w - - 0 1"), Some(EndgameResult::Draw));
+        assert_eq!(result("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1"), None);
+    }
+
+    #[test]
+    fn pawn() {
+        assert_eq!(result("4k3/8/4K3/4P3/8/8/8/8 w - - 0 1").map(|r| matches!(r, EndgameResult::Win(_))), Some(true));
+        assert_eq!(result("4k3/8/4K3/4P3/8/8/8/8 b - - 0 1").map(|r| matches!(r, EndgameResult::Loss(_))), Some(true));
+        assert_eq!(result("4k3/8/8/4P3/4K3/8/8/8 b - - 0 1"), Some(EndgameResult::Draw));
+        assert_eq!(result("4k3/4P3/4K3/8/8/8/8/8 b - - 0 1"), Some(EndgameResult::Draw));
+        // a rook pawn with the king in front is a draw
+        assert_eq!(result("k7/8/8/8/8/8/P7/K7 w - - 0 1"), Some(EndgameResult::Draw));
+    }
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore = "the KBNK table takes minutes to build without optimizations")]
+    fn bishop_and_knight() {
+        let game = Game::from_fen("7k/8/5K2/6N1/8/8/8/3B4 w - - 0 1").unwrap();
+        assert!(matches!(game.endgame_result(), Some(EndgameResult::Win(_))));
+    }
+
+    #[test]
+    fn plays_out_the_win() {
+        let mut game = Game::from_fen("8/8/8/3k4/8/8/8/KR6 w - - 0 1").unwrap();
+        let moves = match game.endgame_result() {
+            Some(EndgameResult::Win(moves)) => moves,
+            other => panic!("{:?}", other),
+        };
+        for _ in 0..2 * moves - 1 {
+            let _move = game.endgame_move().unwrap();
+            game.replay(&_move);
+        }
+        assert_eq!(game.get_game_state(), GameState::GameOver);
+        assert_eq!(game.endgame_result(), Some(EndgameResult::Loss(0)));
+
+        // the pawn promotes to a queen
+        let game = Game::from_fen("8/4P3/8/8/8/8/k7/4K3 w - - 0 1").unwrap();
+        assert_eq!(game.endgame_move(), Some(Move { promotion: Some(crate::PieceType::Queen), ..Move::from_uci("e7e8").unwrap() }));
+    }
+}
@@ -0,0 +1,467 @@
+use std::sync::Arc;
+
+use crate::variant::{Standard, Variant};
+use crate::{square_name, Game, GameState, Piece, PieceColor, PieceType};
+
+pub(crate) const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+impl Game {
+    /// Loads a position from Forsyth-Edwards Notation, e.g.
+    /// "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1".
+    ///
+    /// Castling rights can also be given as X-FEN or Shredder-FEN rook files
+    /// ("HAha"). The game is played as Chess960 if they need to be, i.e. if a
+    /// king or castling rook is not on its standard square. Castling rights
+    /// without a matching king and rook are dropped, and so is an en passant
+    /// square without a pawn that just moved two squares past it. A pawn
+    /// standing on its last rank with its own side to move is loaded as a
+    /// pending promotion, which is how `to_fen` writes that state.
+    pub fn from_fen(fen: &str) -> Option<Game> {
+        Self::load_fen(fen, false, Arc::new(Standard))
+    }
+
+    /// Like `from_fen`, but always plays the game as Chess960, so castling
+    /// is written as the king taking its own rook.
+    pub fn from_fen_chess960(fen: &str) -> Option<Game> {
+        Self::load_fen(fen, true, Arc::new(Standard))
+    }
+
+    /// Like `from_fen`, for a game played by the rules of `variant`.
+    pub fn from_fen_variant(fen: &str, variant: impl Variant + 'static) -> Option<Game> {
+        Self::load_fen(fen, false, Arc::new(variant))
+    }
+
+    pub(crate) fn load_fen(fen: &str, chess960: bool, variant: Arc<dyn Variant>) -> Option<Game> {
+        let mut fields: Vec<&str> = fen.split_whitespace().collect();
+
+        // Three-check positions have the checks each side has left, e.g.
+        // "3+3", after the en passant square.
+        let mut checks = [0, 0];
+        if fields.len() > 4 && fields[4].contains('+') {
+            let limit = variant.check_limit()?;
+            let (white, black) = fields.remove(4).split_once('+')?;
+            let (white, black): (u32, u32) = (white.parse().ok()?, black.parse().ok()?);
+            if white > limit || black > limit {
+                return None;
+            }
+            checks = [limit - white, limit - black];
+        }
+        if fields.len() < 2 || fields.len() > 6 {
+            return None;
+        }
+
+        // Crazyhouse positions have the pieces in the pockets after the
+        // placement, e.g. "[Qn]", and mark promoted pieces with "~".
+        let (placement, pocket) = match fields[0].split_once('[') {
+            Some((placement, pocket)) => (placement, Some(pocket.strip_suffix(']')?)),
+            None => (fields[0], None),
+        };
+        if pocket.is_some() && !variant.uses_pockets() {
+            return None;
+        }
+        let mut board = parse_placement(placement)?;
+        let turn = match fields[1] {
+            "w" => PieceColor::White,
+            "b" => PieceColor::Black,
+            _ => return None,
+        };
+        let (castling, needs_chess960) = parse_castling(&board, fields.get(2).unwrap_or(&"-"))?;
+        let en_passant = match fields.get(3) {
+            Some(&"-") | None => None,
+            Some(square) => Some(crate::square_index(square)?),
+        };
+        let halfmove_clock: u32 = match fields.get(4) {
+            Some(field) => field.parse().ok()?,
+            None => 0,
+        };
+        let fullmove_number: u32 = match fields.get(5) {
+            Some(field) => field.parse().ok()?,
+            None => 1,
+        };
+        if fullmove_number == 0 {
+            return None;
+        }
+
+        let mut promotion_pos: Option<usize> = None;
+        let mut kings = (0, 0);
+        for (pos, piece) in board.iter_mut().enumerate() {
+            let piece = match piece {
+                Some(piece) => piece,
+                None => continue,
+            };
+            match (piece.piecetype, piece.piececolor) {
+                (PieceType::King, PieceColor::White) => kings.0 += 1,
+                (PieceType::King, PieceColor::Black) => kings.1 += 1,
+                (PieceType::Pawn, color) => {
+                    let (last_row, first_row) = match color {
+                        PieceColor::White => (0, 7),
+                        PieceColor::Black => (7, 0),
+                    };
+                    if pos / 8 == first_row {
+                        if !variant.pawns_on_first_rank() {
+                            return None;
+                        }
+                        piece.hasmoved = false;
+                    }
+                    if pos / 8 == last_row {
+                        if color != turn || promotion_pos.is_some() {
+                            return None;
+                        }
+                        promotion_pos = Some(pos);
+                    }
+                }
+                _ => (),
+            }
+        }
+        if variant.kings_required() && kings != (1, 1) {
+            return None;
+        }
+
+        let mut game = Game::with_board(board, turn);
+        game.castling = castling;
+        game.en_passant = en_passant.filter(|pos| en_passant_valid(&game.gameboard, turn, *pos));
+        game.chess960 = chess960 || needs_chess960;
+        game.halfmove_clock = halfmove_clock;
+        game.fullmove_number = fullmove_number;
+        game.variant = variant;
+        game.checks = checks;
+        for c in pocket.unwrap_or("").chars() {
+            let piece = char_to_piece(c)?;
+            if piece.piecetype == PieceType::King {
+                return None;
+            }
+            game.add_to_pocket(piece.piececolor, piece.piecetype);
+        }
+        game.int_to_string = game.int_to_string();
+
+        match promotion_pos {
+            Some(pos) => {
+                game.promotion_pos = Some(pos);
+                game.state = GameState::SetPromotion;
+            }
+            None => game.update_state(),
+        }
+        game.start_fen = game.to_fen();
+
+        Some(game)
+    }
+
+    /// Writes the current position in Forsyth-Edwards Notation. Chess960
+    /// castling rights are written as X-FEN, i.e. "KQkq" when that is enough
+    /// to tell the rooks apart and rook files otherwise.
+    pub fn to_fen(&self) -> String {
+        self.write_fen(false)
+    }
+
+    /// Writes the current position as Shredder-FEN, which names castling
+    /// rooks by their file, e.g. "HAha" for the standard start position.
+    pub fn to_shredder_fen(&self) -> String {
+        self.write_fen(true)
+    }
+
+    fn write_fen(&self, shredder: bool) -> String {
+        let mut fen = write_placement(&self.gameboard, self.variant.uses_pockets());
+        if self.variant.uses_pockets() {
+            fen.push_str(&write_pockets(&self.pockets));
+        }
+
+        match self.turn {
+            PieceColor::White => fen.push_str(" w"),
+            PieceColor::Black => fen.push_str(" b"),
+        }
+        fen.push(' ');
+        fen.push_str(&self.castling_field(shredder));
+        // only written when the capture can be made
+        let en_passant = self.en_passant.filter(|pos| {
+            self.get_legal_moves().iter().any(|_move| _move.to == *pos && self.en_passant_capture(_move).is_some())
+        });
+        match en_passant {
+            Some(pos) => fen.push_str(&format!(" {}", square_name(pos))),
+            None => fen.push_str(" -"),
+        }
+        if let Some(limit) = self.variant.check_limit() {
+            let white = limit.saturating_sub(self.checks[0]);
+            let black = limit.saturating_sub(self.checks[1]);
+            fen.push_str(&format!(" {}+{}", white, black));
+        }
+        fen.push(' ');
+        fen.push_str(&self.halfmove_clock.to_string());
+        fen.push(' ');
+        fen.push_str(&self.fullmove_number.to_string());
+        fen
+    }
+
+    pub(crate) fn castling_field(&self, shredder: bool) -> String {
+        let mut field = String::new();
+        for color in [PieceColor::White, PieceColor::Black] {
+            let row = back_row(color);
+            let mut rooks: Vec<usize> = self.castling.iter().copied().filter(|rook| *rook / 8 == row).collect();
+            rooks.sort_by(|a, b| b.cmp(a));
+            for rook in rooks {
+                let king = find_king(&self.gameboard, color).unwrap_or(rook);
+                let kingside = rook > king;
+                let c = if !shredder && outermost_rook(&self.gameboard, color, kingside) == Some(rook) {
+                    if kingside { 'k' } else { 'q' }
+                } else {
+                    (b'a' + (rook % 8) as u8) as char
+                };
+                match color {
+                    PieceColor::White => field.push(c.to_ascii_uppercase()),
+                    PieceColor::Black => field.push(c),
+                }
+            }
+        }
+        if field.is_empty() {
+            field.push('-');
+        }
+        field
+    }
+
+    /// The position the game started from, in Forsyth-Edwards Notation.
+    pub fn get_start_fen(&self) -> &str {
+        &self.start_fen
+    }
+}
+
+/// The piece placement field, with "~" after promoted pieces if `promoted`.
+pub(crate) fn write_placement(board: &[Option<Piece>], promoted: bool) -> String {
+    let mut fen = String::new();
+    let mut row = 0;
+    while row < 8 {
+        let mut empty = 0;
+        for piece in board[row * 8..row * 8 + 8].iter() {
+            match piece {
+                Some(piece) => {
+                    if empty > 0 {
+                        fen.push_str(&empty.to_string());
+                        empty = 0;
+                    }
+                    fen.push(piece_to_char(piece));
+                    if piece.promoted && promoted {
+                        fen.push('~');
+                    }
+                }
+                None => empty += 1,
+            }
+        }
+        if empty > 0 {
+            fen.push_str(&empty.to_string());
+        }
+        if row < 7 {
+            fen.push('/');
+        }
+        row += 1;
+    }
+    fen
+}
+
+/// The pockets of white and black, e.g. "[Qn]".
+pub(crate) fn write_pockets(pockets: &[Vec<PieceType>; 2]) -> String {
+    let mut field = String::from("[");
+    for color in [PieceColor::White, PieceColor::Black] {
+        for piecetype in pockets[color as usize].iter() {
+            field.push(piece_to_char(&Piece::new(*piecetype, color)));
+        }
+    }
+    field.push(']');
+    field
+}
+
+fn parse_placement(placement: &str) -> Option<Vec<Option<Piece>>> {
+    let rows: Vec<&str> = placement.split('/').collect();
+    if rows.len() != 8 {
+        return None;
+    }
+
+    let mut board: Vec<Option<Piece>> = Vec::new();
+    for (row, fen_row) in rows.iter().enumerate() {
+        let mut count = 0;
+        for c in fen_row.chars() {
+            if c == '~' {
+                match board.last_mut() {
+                    Some(Some(piece)) => piece.promoted = true,
+                    _ => return None,
+                }
+            } else if let Some(empty) = c.to_digit(10) {
+                if empty == 0 || empty > 8 {
+                    return None;
+                }
+                for _x in 0..empty {
+                    board.push(None);
+                }
+                count += empty;
+            } else {
+                let mut piece = char_to_piece(c)?;
+                if piece.piecetype == PieceType::Pawn {
+                    piece.hasmoved = match piece.piececolor {
+                        PieceColor::White => row != 6,
+                        PieceColor::Black => row != 1,
+                    };
+                }
+                board.push(Some(piece));
+                count += 1;
+            }
+        }
+        if count != 8 {
+            return None;
+        }
+    }
+    Some(board)
+}
+
+/// Finds the castling rooks named by a FEN castling field, and tells if they
+/// can only be castled with Chess960 rules.
+pub(crate) fn parse_castling(board: &[Option<Piece>], field: &str) -> Option<(Vec<usize>, bool)> {
+    let mut castling: Vec<usize> = Vec::new();
+    let mut chess960 = false;
+    if field == "-" {
+        return Some((castling, chess960));
+    }
+
+    for c in field.chars() {
+        let color = if c.is_ascii_uppercase() {
+            PieceColor::White
+        } else {
+            PieceColor::Black
+        };
+        let row = back_row(color);
+        let king = match find_king(board, color) {
+            Some(king) if king / 8 == row => king,
+            _ => continue,
+        };
+        let rook = match c.to_ascii_lowercase() {
+            'k' => outermost_rook(board, color, true),
+            'q' => outermost_rook(board, color, false),
+            'a'..='h' => {
+                chess960 = true;
+                let pos = row * 8 + (c.to_ascii_lowercase() as u8 - b'a') as usize;
+                match &board[pos] {
+                    Some(piece) if piece.piecetype == PieceType::Rook && piece.piececolor == color && pos != king => Some(pos),
+                    _ => None,
+                }
+            }
+            _ => return None,
+        };
+        if let Some(rook) = rook {
+            if king % 8 != 4 || (rook % 8 != 0 && rook % 8 != 7) {
+                chess960 = true;
+            }
+            if !castling.contains(&rook) {
+                castling.push(rook);
+            }
+        }
+    }
+    Some((castling, chess960))
+}
+
+/// Tells if a pawn of the side not to move can just have moved two squares
+/// past `pos`, so that `turn` may take it en passant.
+pub(crate) fn en_passant_valid(board: &[Option<Piece>], turn: PieceColor, pos: usize) -> bool {
+    let (pawn, from) = match turn {
+        PieceColor::White if pos / 8 == 2 => (pos + 8, pos - 8),
+        PieceColor::Black if pos / 8 == 5 => (pos - 8, pos + 8),
+        _ => return false,
+    };
+    board[pos].is_none()
+        && board[from].is_none()
+        && matches!(&board[pawn], Some(piece) if piece.piecetype == PieceType::Pawn && piece.piececolor != turn)
+}
+
+fn back_row(color: PieceColor) -> usize {
+    match color {
+        PieceColor::White => 7,
+        PieceColor::Black => 0,
+    }
+}
+
+pub(crate) fn find_king(board: &[Option<Piece>], color: PieceColor) -> Option<usize> {
+    board.iter().position(|piece| match piece {
+        Some(piece) => piece.piecetype == PieceType::King && piece.piececolor == color,
+        None => false,
+    })
+}
+
+/// The rook furthest from the king on the given side of its back rank.
+fn outermost_rook(board: &[Option<Piece>], color: PieceColor, kingside: bool) -> Option<usize> {
+    let row = back_row(color);
+    let king = find_king(board, color)?;
+    if king / 8 != row {
+        return None;
+    }
+    let files: Vec<usize> = if kingside {
+        (king % 8 + 1..8).rev().collect()
+    } else {
+        (0..king % 8).collect()
+    };
+    files.into_iter().map(|file| row * 8 + file).find(|pos| match &board[*pos] {
+        Some(piece) => piece.piecetype == PieceType::Rook && piece.piececolor == color,
+        None => false,
+    })
+}
+
+pub(crate) fn opposite(color: PieceColor) -> PieceColor {
+    match color {
+        PieceColor::White => PieceColor::Black,
+        PieceColor::Black => PieceColor::White,
+    }
+}
+
+pub(crate) fn piece_to_char(piece: &Piece) -> char {
+    let c = match piece.piecetype {
+        PieceType::King => 'k',
+        PieceType::Queen => 'q',
+        PieceType::Bishop => 'b',
+        PieceType::Knight => 'n',
+        PieceType::Rook => 'r',
+        PieceType::Pawn => 'p',
+    };
+    match piece.piececolor {
+        PieceColor::White => c.to_ascii_uppercase(),
+        PieceColor::Black => c,
+    }
+}
+
+pub(crate) fn char_to_piece(c: char) -> Option<Piece> {
+    let piecetype = match c.to_ascii_lowercase() {
+        'k' => PieceType::King,
+        'q' => PieceType::Queen,
+        'b' => PieceType::Bishop,
+        'n' => PieceType::Knight,
+        'r' => PieceType::Rook,
+        'p' => PieceType::Pawn,
+        _ => return None,
+    };
+    let piececolor = if c.is_ascii_uppercase() {
+        PieceColor::White
+    } else {
+        PieceColor::Black
+    };
+    Some(Piece::new(piecetype, piececolor))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Game, GameState};
+
+    #[test]
+    fn start_position_round_trip() {
+        let game = Game::new();
+        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert_eq!(game.to_fen(), fen);
+        assert_eq!(Game::from_fen(fen).unwrap().to_fen(), fen);
+    }
+
+    #[test]
+    fn pending_promotion_is_loaded() {
+        let game = Game::from_fen("1P2k3/8/8/8/8/8/8/4K3 w - - 0 40").unwrap();
+        assert_eq!(game.get_game_state(), GameState::SetPromotion);
+        assert_eq!(game.promotion_pos, Some(1));
+    }
+
+    #[test]
+    fn invalid_fen_is_rejected() {
+        assert!(Game::from_fen("").is_none());
+        assert!(Game::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN w").is_none());
+        assert!(Game::from_fen("8/8/8/8/8/8/8/4K3 w - - 0 1").is_none());
+    }
+}
@@ -0,0 +1,325 @@
+//! C interface, enabled with the `ffi` feature. The matching header,
+//! `include/erikrub_chess.h`, is generated from this file with cbindgen
+//! (settings in `cbindgen.toml`) and checked in, so C users need no Rust
+//! tools. Regenerate it after changing the interface:
+//!
+//! ```text
+//! cbindgen --config cbindgen.toml --output include/erikrub_chess.h src/ffi.rs
+//! ```
+//!
+//! A game is an opaque `ChessGame*` from `chess_game_new` or
+//! `chess_game_from_fen` and must be released with `chess_game_free`.
+//! Squares are numbered like `Game::gameboard`, 0 = a8 to 63 = h1.
+//! No function panics across the boundary: bad arguments, including null
+//! pointers, give `CHESS_ERROR`.
+
+use std::ffi::{c_char, CStr};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use crate::{square_name, Game, GameState, PieceColor, PieceType};
+
+/// Bad arguments, such as a null pointer.
+pub const CHESS_ERROR: i32 = -1;
+pub const CHESS_ILLEGAL_MOVE: i32 = -2;
+
+/// States, the return values that are >= 0.
+pub const CHESS_IN_PROGRESS: i32 = 0;
+pub const CHESS_SET_PROMOTION: i32 = 1;
+pub const CHESS_CHECK: i32 = 2;
+pub const CHESS_GAME_OVER: i32 = 3;
+
+/// Pieces for `chess_game_promote`.
+pub const CHESS_QUEEN: i32 = 1;
+pub const CHESS_BISHOP: i32 = 2;
+pub const CHESS_KNIGHT: i32 = 3;
+pub const CHESS_ROOK: i32 = 4;
+
+/// Colors returned by `chess_game_turn`.
+pub const CHESS_WHITE: i32 = 0;
+pub const CHESS_BLACK: i32 = 1;
+
+pub struct ChessGame {
+    game: Game,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ChessMove {
+    pub from: u8,
+    pub to: u8,
+}
+
+fn state_code(state: GameState) -> i32 {
+    match state {
+        GameState::InProgress => CHESS_IN_PROGRESS,
+        GameState::SetPromotion => CHESS_SET_PROMOTION,
+        GameState::Check => CHESS_CHECK,
+        GameState::GameOver => CHESS_GAME_OVER,
+    }
+}
+
+/// Runs `f` and turns a panic into `error` instead of unwinding into C.
+fn guard<T>(error: T, f: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(error)
+}
+
+/// New game in the standard start position.
+#[no_mangle]
+pub extern "C" fn chess_game_new() -> *mut ChessGame {
+    guard(ptr::null_mut(), || {
+        Box::into_raw(Box::new(ChessGame { game: Game::new() }))
+    })
+}
+
+/// New game from a FEN string, null if the string is not valid.
+///
+/// # Safety
+/// `fen` must be null or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn chess_game_from_fen(fen: *const c_char) -> *mut ChessGame {
+    if fen.is_null() {
+        return ptr::null_mut();
+    }
+    let fen = CStr::from_ptr(fen);
+    guard(ptr::null_mut(), || {
+        match fen.to_str().ok().and_then(Game::from_fen) {
+            Some(game) => Box::into_raw(Box::new(ChessGame { game })),
+            None => ptr::null_mut(),
+        }
+    })
+}
+
+/// # Safety
+/// `game` must be null or a handle that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn chess_game_free(game: *mut ChessGame) {
+    if !game.is_null() {
+        drop(Box::from_raw(game));
+    }
+}
+
+/// Writes up to `capacity` moves of the side to move into `moves` and returns
+/// how many moves there are in total, which can be more than `capacity`.
+///
+/// # Safety
+/// `game` must be a live handle and `moves` must point to at least `capacity`
+/// writable `ChessMove`s (it may be null when `capacity` is 0).
+#[no_mangle]
+pub unsafe extern "C" fn chess_game_legal_moves(
+    game: *const ChessGame,
+    moves: *mut ChessMove,
+    capacity: usize,
+) -> i32 {
+    let game = match game.as_ref() {
+        Some(game) => game,
+        None => return CHESS_ERROR,
+    };
+    if moves.is_null() && capacity > 0 {
+        return CHESS_ERROR;
+    }
+    guard(CHESS_ERROR, || {
+        let legal = game.game.get_legal_moves();
+        for (i, _move) in legal.iter().take(capacity).enumerate() {
+            *moves.add(i) = ChessMove {
+                from: _move.from as u8,
+                to: _move.to as u8,
+            };
+        }
+        legal.len() as i32
+    })
+}
+
+/// Makes a move and returns the new state, or `CHESS_ILLEGAL_MOVE` if the
+/// move is not allowed.
+///
+/// # Safety
+/// `game` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn chess_game_make_move(game: *mut ChessGame, from: u8, to: u8) -> i32 {
+    let game = match game.as_mut() {
+        Some(game) => game,
+        None => return CHESS_ERROR,
+    };
+    if from > 63 || to > 63 {
+        return CHESS_ERROR;
+    }
+    guard(CHESS_ERROR, || {
+        let game = &mut game.game;
+        let played = game.get_history().len();
+        match game.make_move(&square_name(from as usize), &square_name(to as usize)) {
+            Some(state) if game.get_history().len() > played => state_code(state),
+            Some(_) => CHESS_ILLEGAL_MOVE,
+            None => CHESS_ERROR,
+        }
+    })
+}
+
+/// Chooses the piece for a pending promotion (one of the `CHESS_QUEEN`,
+/// `CHESS_BISHOP`, `CHESS_KNIGHT`, `CHESS_ROOK` values) and returns the new
+/// state. Gives `CHESS_ILLEGAL_MOVE` if no promotion is pending.
+///
+/// # Safety
+/// `game` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn chess_game_promote(game: *mut ChessGame, piece: i32) -> i32 {
+    let game = match game.as_mut() {
+        Some(game) => game,
+        None => return CHESS_ERROR,
+    };
+    let piecetype = match piece {
+        CHESS_QUEEN => PieceType::Queen,
+        CHESS_BISHOP => PieceType::Bishop,
+        CHESS_KNIGHT => PieceType::Knight,
+        CHESS_ROOK => PieceType::Rook,
+        _ => return CHESS_ERROR,
+    };
+    guard(CHESS_ERROR, || {
+        let game = &mut game.game;
+        if game.promotion_pos.is_none() {
+            return CHESS_ILLEGAL_MOVE;
+        }
+        game.promote(piecetype);
+        state_code(game.get_game_state())
+    })
+}
+
+/// The state of the game, one of the `CHESS_IN_PROGRESS` to
+/// `CHESS_GAME_OVER` values.
+///
+/// # Safety
+/// `game` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn chess_game_state(game: *const ChessGame) -> i32 {
+    match game.as_ref() {
+        Some(game) => state_code(game.game.get_game_state()),
+        None => CHESS_ERROR,
+    }
+}
+
+/// `CHESS_WHITE` or `CHESS_BLACK`, the side to move.
+///
+/// # Safety
+/// `game` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn chess_game_turn(game: *const ChessGame) -> i32 {
+    match game.as_ref() {
+        Some(game) => match game.game.turn {
+            PieceColor::White => CHESS_WHITE,
+            PieceColor::Black => CHESS_BLACK,
+        },
+        None => CHESS_ERROR,
+    }
+}
+
+/// Takes back the last move and returns the new state, or
+/// `CHESS_ILLEGAL_MOVE` if no move has been made.
+///
+/// # Safety
+/// `game` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn chess_game_undo(game: *mut ChessGame) -> i32 {
+    let game = match game.as_mut() {
+        Some(game) => game,
+        None => return CHESS_ERROR,
+    };
+    guard(CHESS_ERROR, || match game.game.undo() {
+        Some(state) => state_code(state),
+        None => CHESS_ILLEGAL_MOVE,
+    })
+}
+
+/// Writes the position as a NUL-terminated FEN string into `buffer` and
+/// returns its length without the NUL. Like `snprintf`, nothing is written
+/// if `capacity` is too small, so the caller can retry with a bigger buffer.
+///
+/// # Safety
+/// `game` must be a live handle and `buffer` must point to at least
+/// `capacity` writable bytes (it may be null when `capacity` is 0).
+#[no_mangle]
+pub unsafe extern "C" fn chess_game_fen(
+    game: *const ChessGame,
+    buffer: *mut c_char,
+    capacity: usize,
+) -> i32 {
+    let game = match game.as_ref() {
+        Some(game) => game,
+        None => return CHESS_ERROR,
+    };
+    if buffer.is_null() && capacity > 0 {
+        return CHESS_ERROR;
+    }
+    guard(CHESS_ERROR, || {
+        let fen = game.game.to_fen();
+        if fen.len() < capacity {
+            ptr::copy_nonoverlapping(fen.as_ptr() as *const c_char, buffer, fen.len());
+            *buffer.add(fen.len()) = 0;
+        }
+        fen.len() as i32
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    #[test]
+    fn play_through_handle() {
+        unsafe {
+            let game = chess_game_new();
+            assert_eq!(chess_game_legal_moves(game, ptr::null_mut(), 0), 20);
+
+            let mut moves = [ChessMove { from: 0, to: 0 }; 4];
+            assert_eq!(chess_game_legal_moves(game, moves.as_mut_ptr(), 4), 20);
+            assert_eq!(moves[0], ChessMove { from: 48, to: 40 });
+
+            // e2e4, then an illegal move and out of range squares
+            assert_eq!(chess_game_make_move(game, 52, 36), 0);
+            assert_eq!(chess_game_turn(game), 1);
+            assert_eq!(chess_game_make_move(game, 12, 36), CHESS_ILLEGAL_MOVE);
+            assert_eq!(chess_game_make_move(game, 12, 64), CHESS_ERROR);
+            assert_eq!(chess_game_promote(game, 1), CHESS_ILLEGAL_MOVE);
+            assert_eq!(chess_game_undo(game), 0);
+            assert_eq!(chess_game_undo(game), CHESS_ILLEGAL_MOVE);
+
+            chess_game_free(game);
+        }
+    }
+
+    #[test]
+    fn fen_and_promotion() {
+        unsafe {
+            let fen = CString::new("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+            let game = chess_game_from_fen(fen.as_ptr());
+            assert!(!game.is_null());
+            assert_eq!(chess_game_make_move(game, 9, 1), 1);
+            assert_eq!(chess_game_promote(game, 7), CHESS_ERROR);
+            assert_eq!(chess_game_promote(game, 1), 2);
+
+            let mut buffer = [0 as c_char; 64];
+            assert_eq!(chess_game_fen(game, buffer.as_mut_ptr(), 4), 31);
+            assert_eq!(buffer[0], 0);
+            assert_eq!(chess_game_fen(game, buffer.as_mut_ptr(), buffer.len()), 31);
+            let written = CStr::from_ptr(buffer.as_ptr()).to_str().unwrap();
+            assert_eq!(written, "1Q2k3/8/8/8/8/8/8/4K3 b - - 0 1");
+            chess_game_free(game);
+
+            let bad = CString::new("not a fen").unwrap();
+            assert!(chess_game_from_fen(bad.as_ptr()).is_null());
+            assert!(chess_game_from_fen(ptr::null()).is_null());
+            assert_eq!(chess_game_state(ptr::null()), CHESS_ERROR);
+        }
+    }
+
+    #[test]
+    fn header_declares_every_function() {
+        let header = include_str!("../include/erikrub_chess.h");
+        for line in include_str!("ffi.rs").lines() {
+            if let Some(rest) = line.split("extern \"C\" fn ").nth(1) {
+                let name = rest.split('(').next().unwrap();
+                assert!(header.contains(&format!("{}(", name)), "{} missing in header", name);
+            }
+        }
+    }
+}
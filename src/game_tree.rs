@@ -0,0 +1,579 @@
+//! A game with variations and annotations, for analysis boards and studies.
+//!
+//! ```
+//! use erikrub_chess_lib::game_tree::GameTree;
+//!
+//! let mut tree = GameTree::from_pgn("1. e4 e5 (1... c5 $1 {Sicilian}) 2. Nf3 *").unwrap();
+//! tree.go_to_start();
+//! assert!(tree.go_forward());
+//! tree.play_san("Nc6").unwrap();
+//! assert!(tree.to_pgn().ends_with("1. e4 e5 (1... c5 $1 {Sicilian}) (1... Nc6) 2. Nf3 *\n"));
+//! ```
+
+use crate::pgn::{read_pgn, wrap_movetext, Tags};
+use crate::{square_index, Game, GameState, Move, PieceColor};
+
+/// An arrow drawn on the board, `[%cal Ge2e4]` in a PGN comment. `color`
+/// is the letter of the color, e.g. 'G', 'R', 'Y' or 'B'.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Arrow {
+    pub color: char,
+    pub from: String,
+    pub to: String,
+}
+
+/// A highlighted square, `[%csl Rd4]` in a PGN comment.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Highlight {
+    pub color: char,
+    pub square: String,
+}
+
+/// A move in the tree and what was said about it. The root has no move.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Node {
+    _move: Option<Move>,
+    pub comment: String,
+    /// Numeric Annotation Glyphs, e.g. 1 for "!", 2 for "?" and 5 for "!?".
+    pub nags: Vec<u8>,
+    pub arrows: Vec<Arrow>,
+    pub highlights: Vec<Highlight>,
+    children: Vec<Node>,
+}
+
+impl Node {
+    pub fn get_move(&self) -> Option<Move> {
+        self._move
+    }
+
+    /// The moves played after this one, the main line first.
+    pub fn children(&self) -> &[Node] {
+        &self.children
+    }
+
+    /// Adds PGN comment text, taking out arrows and highlights.
+    fn add_comment(&mut self, text: &str) {
+        let mut rest = String::new();
+        let mut text = text;
+        while let Some(start) = text.find("[%") {
+            let end = match text[start..].find(']') {
+                Some(end) => start + end,
+                None => break,
+            };
+            rest.push_str(&text[..start]);
+            match text[start + 2..end].split_once(' ') {
+                Some(("cal", arrows)) => {
+                    for arrow in arrows.split(',').map(str::trim) {
+                        if arrow.len() == 5 && arrow.is_ascii() && square_index(&arrow[1..3]).is_some() && square_index(&arrow[3..5]).is_some() {
+                            self.arrows.push(Arrow {
+                                color: arrow.as_bytes()[0] as char,
+                                from: arrow[1..3].to_string(),
+                                to: arrow[3..5].to_string(),
+                            });
+                        }
+                    }
+                }
+                Some(("csl", squares)) => {
+                    for square in squares.split(',').map(str::trim) {
+                        if square.len() == 3 && square.is_ascii() && square_index(&square[1..3]).is_some() {
+                            self.highlights.push(Highlight {
+                                color: square.as_bytes()[0] as char,
+                                square: square[1..3].to_string(),
+                            });
+                        }
+                    }
+                }
+                // other commands, e.g. clock times, stay in the comment
+                _ => rest.push_str(&text[start..=end]),
+            }
+            text = &text[end + 1..];
+        }
+        rest.push_str(text);
+        let rest = rest.split_whitespace().collect::<Vec<&str>>().join(" ");
+        if !rest.is_empty() {
+            if !self.comment.is_empty() {
+                self.comment.push(' ');
+            }
+            self.comment.push_str(&rest);
+        }
+    }
+
+    /// The PGN comment with the arrows and highlights, None if there is
+    /// nothing to say.
+    fn pgn_comment(&self) -> Option<String> {
+        let mut parts: Vec<String> = Vec::new();
+        if !self.highlights.is_empty() {
+            let squares: Vec<String> = self.highlights.iter().map(|h| format!("{}{}", h.color, h.square)).collect();
+            parts.push(format!("[%csl {}]", squares.join(",")));
+        }
+        if !self.arrows.is_empty() {
+            let arrows: Vec<String> = self.arrows.iter().map(|a| format!("{}{}{}", a.color, a.from, a.to)).collect();
+            parts.push(format!("[%cal {}]", arrows.join(",")));
+        }
+        if !self.comment.is_empty() {
+            parts.push(self.comment.clone());
+        }
+        if parts.is_empty() {
+            None
+        } else {
+            Some(format!("{{{}}}", parts.join(" ")))
+        }
+    }
+}
+
+/// A main line with nested variations, and a cursor at one of its moves.
+#[derive(Clone)]
+pub struct GameTree {
+    root: Node,
+    start: Game,
+    game: Game,
+    path: Vec<usize>, // child indices from the root to the cursor
+    tags: Tags,
+}
+
+impl GameTree {
+    /// A tree with the moves of `game` as its main line, and the cursor at
+    /// its current position. None if `game` is waiting for `set_promotion`,
+    /// as the tree only holds whole moves.
+    pub fn new(game: &Game) -> Option<GameTree> {
+        if game.get_game_state() == GameState::SetPromotion {
+            return None;
+        }
+        let start = game.start_position();
+        let mut tree = GameTree {
+            root: Node::default(),
+            game: start.clone(),
+            start,
+            path: Vec::new(),
+            tags: Vec::new(),
+        };
+        for _move in game.get_history() {
+            tree.play(_move)?;
+        }
+        Some(tree)
+    }
+
+    /// Loads the first game of a PGN text with its variations, comments,
+    /// NAGs, arrows and tags. The cursor is at the end of the main line. None
+    /// if a move is not allowed, a variation is not closed, or the FEN or
+    /// Variant tag is not valid.
+    pub fn from_pgn(pgn: &str) -> Option<GameTree> {
+        let (start, tags, movetext) = read_pgn(pgn)?;
+        let mut tree = GameTree {
+            root: Node::default(),
+            game: start.clone(),
+            start,
+            path: Vec::new(),
+            tags,
+        };
+        let mut variations: Vec<Vec<usize>> = Vec::new();
+        for token in tokenize(&movetext) {
+            match token {
+                Token::San(san) => {
+                    tree.play_san(&san)?;
+                }
+                Token::Nag(nag) => tree.node_mut().nags.push(nag),
+                Token::Comment(text) => tree.node_mut().add_comment(&text),
+                // a variation is played instead of the last move
+                Token::Open => {
+                    variations.push(tree.path.clone());
+                    if !tree.go_back() {
+                        return None;
+                    }
+                }
+                Token::Close => {
+                    let path = variations.pop()?;
+                    tree.go_to(path);
+                }
+            }
+        }
+        if !variations.is_empty() {
+            return None;
+        }
+        tree.go_to_end();
+        Some(tree)
+    }
+
+    /// The tree in Portable Game Notation, with all variations and
+    /// annotations. The result is the outcome at the end of the main line,
+    /// or else the "Result" tag.
+    pub fn to_pgn(&self) -> String {
+        let mut end = self.start.clone();
+        let mut node = &self.root;
+        while let Some(child) = node.children.first() {
+            end.replay(&child._move.unwrap());
+            node = child;
+        }
+        let result = match end.get_outcome() {
+            Some(outcome) => outcome.result(),
+            None => self.get_tag("Result").unwrap_or("*"),
+        };
+        let mut pgn = self.start.pgn_header(&self.tags, result);
+
+        let mut tokens: Vec<String> = Vec::new();
+        tokens.extend(self.root.pgn_comment());
+        write_line(&self.root, &self.start, true, &mut tokens);
+        tokens.push(result.to_string());
+        pgn.push_str(&wrap_movetext(&tokens));
+        pgn
+    }
+
+    /// The position at the cursor.
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// The node at the cursor, the root before the first move.
+    pub fn node(&self) -> &Node {
+        node_at(&self.root, &self.path)
+    }
+
+    /// The node at the cursor, to annotate it.
+    pub fn node_mut(&mut self) -> &mut Node {
+        let mut node = &mut self.root;
+        for index in self.path.iter() {
+            node = &mut node.children[*index];
+        }
+        node
+    }
+
+    pub fn root(&self) -> &Node {
+        &self.root
+    }
+
+    /// Plays a move from the cursor. It follows the move if the tree has it,
+    /// or else adds it, as the main line if there are no moves after the
+    /// cursor and as a new variation if there are. A promotion needs its
+    /// piece. Returns the new state, or None if the move is not allowed.
+    pub fn play(&mut self, _move: &Move) -> Option<GameState> {
+        if !self.game.moves_with_promotions().contains(_move) {
+            return None;
+        }
+        let node = self.node_mut();
+        let index = match node.children.iter().position(|child| child._move == Some(*_move)) {
+            Some(index) => index,
+            None => {
+                node.children.push(Node {
+                    _move: Some(*_move),
+                    ..Node::default()
+                });
+                node.children.len() - 1
+            }
+        };
+        self.path.push(index);
+        self.game.replay(_move);
+        Some(self.game.get_game_state())
+    }
+
+    /// Plays a move in standard algebraic notation like `play`.
+    pub fn play_san(&mut self, san: &str) -> Option<GameState> {
+        let _move = self.game.parse_san(san)?;
+        self.play(&_move)
+    }
+
+    /// Moves the cursor one move along the main line. False at its end.
+    pub fn go_forward(&mut self) -> bool {
+        self.enter_variation(0)
+    }
+
+    /// Moves the cursor to the first move of a variation after it, 0 being
+    /// the main line. False if there is no such variation.
+    pub fn enter_variation(&mut self, index: usize) -> bool {
+        let _move = match self.node().children.get(index) {
+            Some(child) => child._move.unwrap(),
+            None => return false,
+        };
+        self.path.push(index);
+        self.game.replay(&_move);
+        true
+    }
+
+    /// Moves the cursor back one move. False at the start.
+    pub fn go_back(&mut self) -> bool {
+        if self.path.pop().is_none() {
+            return false;
+        }
+        self.game.undo();
+        true
+    }
+
+    pub fn go_to_start(&mut self) {
+        self.go_to(Vec::new());
+    }
+
+    /// Moves the cursor to the end of the line it is in.
+    pub fn go_to_end(&mut self) {
+        while self.go_forward() {}
+    }
+
+    /// Moves the variation the cursor is in one place up among the moves it
+    /// branches off from, so only the first variation becomes the main line.
+    /// `promote_to_main_line` moves it all the way. False if the cursor is in
+    /// the main line.
+    pub fn promote_variation(&mut self) -> bool {
+        let branch = match self.path.iter().rposition(|index| *index > 0) {
+            Some(branch) => branch,
+            None => return false,
+        };
+        let index = self.path[branch];
+        let mut parent = &mut self.root;
+        for index in self.path[..branch].iter() {
+            parent = &mut parent.children[*index];
+        }
+        parent.children.swap(index - 1, index);
+        self.path[branch] = index - 1;
+        true
+    }
+
+    /// Makes the line the cursor is in the main line, moving it to the first
+    /// place at every point it branches off. False if the cursor is in the
+    /// main line.
+    pub fn promote_to_main_line(&mut self) -> bool {
+        if self.path.iter().all(|index| *index == 0) {
+            return false;
+        }
+        let mut node = &mut self.root;
+        for index in self.path.iter_mut() {
+            let child = node.children.remove(*index);
+            node.children.insert(0, child);
+            *index = 0;
+            node = &mut node.children[0];
+        }
+        true
+    }
+
+    /// Deletes the variation the cursor is in and moves the cursor to where
+    /// it branched off. False if the cursor is in the main line.
+    pub fn delete_variation(&mut self) -> bool {
+        let branch = match self.path.iter().rposition(|index| *index > 0) {
+            Some(branch) => branch,
+            None => return false,
+        };
+        let mut parent = &mut self.root;
+        for index in self.path[..branch].iter() {
+            parent = &mut parent.children[*index];
+        }
+        parent.children.remove(self.path[branch]);
+        self.path.truncate(branch);
+        self.go_to(self.path.clone());
+        true
+    }
+
+    /// The moves of the main line.
+    pub fn mainline(&self) -> Vec<Move> {
+        let mut moves: Vec<Move> = Vec::new();
+        let mut node = &self.root;
+        while let Some(child) = node.children.first() {
+            moves.push(child._move.unwrap());
+            node = child;
+        }
+        moves
+    }
+
+    /// The value of a PGN tag, e.g. "White". Tags set from the position,
+    /// like "FEN", are not kept.
+    pub fn get_tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(tag, _)| tag == name).map(|(_, value)| value.as_str())
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, old)) => *old = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    fn go_to(&mut self, path: Vec<usize>) {
+        self.game = self.start.clone();
+        let mut node = &self.root;
+        for index in path.iter() {
+            node = &node.children[*index];
+            self.game.replay(&node._move.unwrap());
+        }
+        self.path = path;
+    }
+}
+
+impl Default for GameTree {
+    fn default() -> Self {
+        GameTree::new(&Game::new()).unwrap()
+    }
+}
+
+fn node_at<'a>(root: &'a Node, path: &[usize]) -> &'a Node {
+    path.iter().fold(root, |node, index| &node.children[*index])
+}
+
+/// Writes the moves after `parent`, which is played in `game`. `number`
+/// asks for the move number even if black is to move.
+fn write_line(parent: &Node, game: &Game, mut number: bool, tokens: &mut Vec<String>) {
+    let mut parent = parent;
+    let mut game = game.clone();
+    while let Some(main) = parent.children.first() {
+        write_move(main, &game, number, tokens);
+        for variation in parent.children[1..].iter() {
+            let mut moves: Vec<String> = Vec::new();
+            write_move(variation, &game, true, &mut moves);
+            let mut after = game.clone();
+            after.replay(&variation._move.unwrap());
+            write_line(variation, &after, variation.pgn_comment().is_some(), &mut moves);
+            moves[0].insert(0, '(');
+            moves.last_mut().unwrap().push(')');
+            tokens.extend(moves);
+        }
+        number = parent.children.len() > 1 || main.pgn_comment().is_some();
+        game.replay(&main._move.unwrap());
+        parent = main;
+    }
+}
+
+fn write_move(node: &Node, game: &Game, number: bool, tokens: &mut Vec<String>) {
+    let _move = node._move.unwrap();
+    if game.turn == PieceColor::White {
+        tokens.push(format!("{}.", game.fullmove_number));
+    } else if number {
+        tokens.push(format!("{}...", game.fullmove_number));
+    }
+    tokens.push(game.move_to_san(&_move).unwrap());
+    tokens.extend(node.nags.iter().map(|nag| format!("${}", nag)));
+    tokens.extend(node.pgn_comment());
+}
+
+enum Token {
+    San(String),
+    Nag(u8),
+    Comment(String),
+    Open,
+    Close,
+}
+
+/// Splits PGN movetext into moves, NAGs, comments and the start and end of
+/// variations. Move numbers and results are dropped.
+fn tokenize(movetext: &str) -> Vec<Token> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut word = String::new();
+    let mut chars = movetext.chars();
+    loop {
+        let c = chars.next();
+        if !matches!(c, Some(c) if !c.is_whitespace() && !"{;()".contains(c)) {
+            push_word(&word, &mut tokens);
+            word.clear();
+        }
+        match c {
+            None => break,
+            Some('{') => tokens.push(Token::Comment(chars.by_ref().take_while(|c| *c != '}').collect())),
+            Some(';') => tokens.push(Token::Comment(chars.by_ref().take_while(|c| *c != '\n').collect())),
+            Some('(') => tokens.push(Token::Open),
+            Some(')') => tokens.push(Token::Close),
+            Some(c) if c.is_whitespace() => (),
+            Some(c) => word.push(c),
+        }
+    }
+    tokens
+}
+
+/// Adds a word of movetext: a NAG, or a move with its "!" and "?" as a NAG.
+fn push_word(word: &str, tokens: &mut Vec<Token>) {
+    if let Some(nag) = word.strip_prefix('$') {
+        tokens.extend(nag.parse().ok().map(Token::Nag));
+        return;
+    }
+    let word = word.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+    if word.is_empty() || ["*", "1-0", "0-1", "1/2-1/2"].contains(&word) {
+        return;
+    }
+    let san = word.trim_end_matches(['!', '?']);
+    let nag = match &word[san.len()..] {
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => None,
+    };
+    tokens.push(Token::San(san.to_string()));
+    tokens.extend(nag.map(Token::Nag));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Arrow, GameTree, Highlight};
+    use crate::{Game, Move};
+
+    #[test]
+    fn variations() {
+        let mut game = Game::new();
+        game.make_san_move("e4");
+        game.make_san_move("e5");
+        let mut tree = GameTree::new(&game).unwrap();
+        assert_eq!(tree.game().to_fen(), game.to_fen());
+        assert!(tree.go_back());
+        tree.play_san("c5").unwrap();
+        tree.play_san("Nf3").unwrap();
+        assert!(tree.go_back() && tree.go_back());
+        tree.play_san("e6").unwrap();
+        assert_eq!(tree.node().children().len(), 0);
+        assert_eq!(tree.mainline(), vec![Move::from_uci("e2e4").unwrap(), Move::from_uci("e7e5").unwrap()]);
+        assert!(tree.to_pgn().ends_with("1. e4 e5 (1... c5 2. Nf3) (1... e6) *\n"));
+
+        // e6 becomes the first variation, then c5 the main line
+        assert!(tree.promote_variation());
+        assert!(tree.to_pgn().ends_with("1. e4 e5 (1... e6) (1... c5 2. Nf3) *\n"));
+        tree.go_to_start();
+        tree.go_forward();
+        assert!(!tree.promote_variation());
+        assert!(tree.enter_variation(2) && tree.go_forward());
+        assert!(tree.promote_variation() && tree.promote_variation());
+        assert!(!tree.promote_variation());
+        assert!(tree.to_pgn().ends_with("1. e4 c5 (1... e5) (1... e6) 2. Nf3 *\n"));
+
+        assert!(tree.go_back() && tree.go_back());
+        assert!(tree.enter_variation(1));
+        assert!(tree.delete_variation());
+        assert_eq!(tree.game().get_history().len(), 1);
+        assert!(tree.to_pgn().ends_with("1. e4 c5 (1... e6) 2. Nf3 *\n"));
+
+        // a line two variations deep goes all the way up
+        assert!(tree.enter_variation(1));
+        tree.play_san("d4").unwrap();
+        assert!(tree.go_back());
+        tree.play_san("Nf3").unwrap();
+        assert!(tree.promote_to_main_line());
+        assert!(!tree.promote_to_main_line());
+        assert!(tree.to_pgn().ends_with("1. e4 e6 (1... c5 2. Nf3) 2. Nf3 (2. d4) *\n"));
+
+        let mut game = Game::from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        game.make_move("b7", "b8");
+        assert!(GameTree::new(&game).is_none());
+    }
+
+    #[test]
+    fn pgn_round_trip() {
+        let pgn = "[Event \"Club\"]\n[Annotator \"Anna\"]\n\n{Opening} 1. e4! e5 2. Nf3 Nc6 (2... d6 {[%clk 0:05:00] Philidor} 3. d4 \
+                   (3. Bc4)) 3. Bb5 $5 {[%csl Rc6][%cal Gb5c6,Ye1g1] pins} 3... a6 *";
+        let tree = GameTree::from_pgn(pgn).unwrap();
+        assert_eq!(tree.game().get_history().len(), 6);
+        assert_eq!(tree.get_tag("Annotator"), Some("Anna"));
+        assert_eq!(tree.root().comment, "Opening");
+        let bb5 = &tree.root().children()[0].children()[0].children()[0].children()[0].children()[0];
+        assert_eq!(bb5.nags, vec![5]);
+        assert_eq!(bb5.comment, "pins");
+        assert_eq!(bb5.highlights, vec![Highlight { color: 'R', square: "c6".to_string() }]);
+        assert_eq!(bb5.arrows[1], Arrow { color: 'Y', from: "e1".to_string(), to: "g1".to_string() });
+
+        let written = tree.to_pgn();
+        assert!(written.starts_with("[Event \"Club\"]\n[Site \"?\"]"));
+        assert!(written.contains("[Result \"*\"]\n[Annotator \"Anna\"]\n\n"));
+        assert!(written.ends_with(
+            "{Opening} 1. e4 $1 e5 2. Nf3 Nc6 (2... d6 {[%clk 0:05:00] Philidor} 3. d4 (3.\n\
+             Bc4)) 3. Bb5 $5 {[%csl Rc6] [%cal Gb5c6,Ye1g1] pins} 3... a6 *\n"
+        ));
+        let again = GameTree::from_pgn(&written).unwrap();
+        assert_eq!(again.root(), tree.root());
+        assert_eq!(again.to_pgn(), written);
+
+        assert!(GameTree::from_pgn("1. e4 (1. d4 *").is_none());
+        assert!(GameTree::from_pgn("1. e4 e5 2. Ke3").is_none());
+    }
+}
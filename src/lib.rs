@@ -2,8 +2,38 @@ use std::cmp;
 use std::collections::HashMap;
 use std::fmt;
 use std::str;
+use std::sync::Arc;
+
+mod attacks;
+mod chess960;
+mod eco;
+mod endgame;
+mod fen;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod game_tree;
+mod make_unmake;
+mod mate;
+pub mod movegen;
+pub mod opening_book;
+mod pgn;
+pub mod puzzle;
+#[cfg(feature = "python")]
+pub mod python;
+mod see;
+#[cfg(feature = "serde")]
+mod serialization;
+pub mod setup;
+#[cfg(feature = "syzygy")]
+pub mod syzygy;
+pub mod variant;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "wasm", wasm_bindgen::prelude::wasm_bindgen)]
+#[cfg_attr(feature = "python", pyo3::pyclass(eq, eq_int, from_py_object, module = "erikrub_chess_lib"))]
 pub enum GameState {
     InProgress,
     SetPromotion,
@@ -11,6 +41,45 @@ pub enum GameState {
     GameOver,
 }
 
+/// How a game ended. `winner` is None for a draw, and `reason` tells what
+/// ended it, e.g. "checkmate" or "stalemate".
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Outcome {
+    pub winner: Option<PieceColor>,
+    pub reason: &'static str,
+}
+
+impl Outcome {
+    pub fn win(winner: PieceColor, reason: &'static str) -> Outcome {
+        Outcome {
+            winner: Some(winner),
+            reason,
+        }
+    }
+
+    pub fn draw(reason: &'static str) -> Outcome {
+        Outcome {
+            winner: None,
+            reason,
+        }
+    }
+
+    /// The result as written in PGN: "1-0", "0-1" or "1/2-1/2".
+    pub fn result(&self) -> &'static str {
+        match self.winner {
+            Some(PieceColor::White) => "1-0",
+            Some(PieceColor::Black) => "0-1",
+            None => "1/2-1/2",
+        }
+    }
+}
+
+pub use attacks::Pin;
+pub use endgame::EndgameResult;
+pub use eco::Opening;
+pub use make_unmake::UndoInfo;
+use variant::{Standard, Variant};
+
 #[derive(Clone)]
 pub struct Game {
     state: GameState,
@@ -23,67 +92,244 @@ pub struct Game {
     movements: Vec<i16>,
     direction_finder: HashMap<i16, i16>,
     pub promotion_pos: Option<usize>,
+    start_fen: String,
+    history: Vec<Move>,
+    undo_records: Vec<UndoRecord>, // one for each move in history
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    castling: Vec<usize>, // rooks that can still castle
+    en_passant: Option<usize>, // square behind a pawn that just moved two squares
+    chess960: bool,
+    variant: Arc<dyn Variant>,
+    outcome: Option<Outcome>,
+    checks: [u32; 2], // checks given by white and black
+    pockets: [Vec<PieceType>; 2], // pieces white and black can drop
+    possible_drops: Vec<Move>,
 }
 
 impl Game {
     pub fn new() -> Self {
-        let mut game = Game {
+        let mut game = Self::with_board(Self::new_board(), PieceColor::White);
+        game.castling = vec![63, 56, 7, 0];
+        game.start_fen = game.to_fen();
+
+        game.firstload();
+
+        game
+    }
+
+    fn with_board(gameboard: Vec<Option<Piece>>, turn: PieceColor) -> Self {
+        Game {
             state: GameState::InProgress,
-            turn: PieceColor::White,
+            turn,
             movements: vec![-8, -7, 1, 9, 8, 7, -1, -9],
             direction_finder: HashMap::from([(7, 5), (8, 4), (9, 3), (-7, 1), (-8, 0), (-9, 7)]),
             distances: Self::generate_distances(),
             string_to_int: Self::string_to_int(),
             int_to_string: HashMap::new(),
-            gameboard: Self::new_board(),
+            gameboard,
             possible_moves: HashMap::new(),
             promotion_pos: None,
-        };
-
-        game.firstload();
+            start_fen: String::new(),
+            history: Vec::new(),
+            undo_records: Vec::new(),
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            castling: Vec::new(),
+            en_passant: None,
+            chess960: false,
+            variant: Arc::new(Standard),
+            outcome: None,
+            checks: [0, 0],
+            pockets: [Vec::new(), Vec::new()],
+            possible_drops: Vec::new(),
+        }
+    }
 
-        game
+    /// New game of a variant, from the variant's start position.
+    pub fn new_variant(variant: impl Variant + 'static) -> Game {
+        let fen = variant.start_fen().to_string();
+        Self::load_fen(&fen, false, Arc::new(variant)).unwrap()
     }
 
     fn firstload(&mut self) {
-        self.possible_moves = self.get_all_possible_moves(self.turn).0;
         self.int_to_string = self.int_to_string();
+        self.update_state();
+    }
+
+    /// Works out the moves, state and outcome for the side to move.
+    fn update_state(&mut self) {
+        (self.possible_moves, self.possible_drops) = self.legal_move_map();
+        self.outcome = self.variant.outcome(self);
+        self.state = if self.outcome.is_some() {
+            self.possible_moves = HashMap::new();
+            self.possible_drops = Vec::new();
+            GameState::GameOver
+        } else if self.is_check() {
+            GameState::Check
+        } else {
+            GameState::InProgress
+        };
+    }
+
+    /// Counts a check given by the move just made.
+    fn count_check(&mut self) {
+        if self.is_check() {
+            self.checks[fen::opposite(self.turn) as usize] += 1;
+        }
+    }
+
+    /// Passes the turn to the other side after a move.
+    fn finish_turn(&mut self) {
+        self.pass_turn();
+        self.count_check();
+        self.update_state();
+    }
+
+    /// Gives the turn to the other side, counting the full moves.
+    fn pass_turn(&mut self) {
+        if self.turn == PieceColor::White {
+            self.turn = PieceColor::Black;
+        } else {
+            self.turn = PieceColor::White;
+            self.fullmove_number += 1;
+        }
+    }
+
+    /// The moves of the side to move that the variant allows, by square, and
+    /// the drops.
+    fn legal_move_map(&self) -> (HashMap<usize, Vec<usize>>, Vec<Move>) {
+        let mut map = self.get_all_possible_moves(self.turn).0;
+        let mut positions: Vec<usize> = map.keys().copied().collect();
+        positions.sort();
+
+        let mut moves: Vec<Move> = Vec::new();
+        for pos in positions {
+            for newpos in map.get(&pos).unwrap().iter() {
+                let _move = Move::new(pos, *newpos);
+                if self.variant.is_legal(self, &_move) {
+                    moves.push(_move);
+                }
+            }
+        }
+        for _move in self.pseudo_drops() {
+            if self.variant.is_legal(self, &_move) {
+                moves.push(_move);
+            }
+        }
+        self.variant.filter_moves(self, &mut moves);
+
+        for list in map.values_mut() {
+            list.clear();
+        }
+        let mut drops: Vec<Move> = Vec::new();
+        for _move in moves {
+            if _move.drop.is_some() {
+                drops.push(_move);
+            } else {
+                map.entry(_move.from).or_default().push(_move.to);
+            }
+        }
+        (map, drops)
+    }
+
+    /// Drops of pieces in the pocket of the side to move onto empty squares.
+    /// Pawns can not be dropped on the first or last rank.
+    fn pseudo_drops(&self) -> Vec<Move> {
+        let mut moves: Vec<Move> = Vec::new();
+        let pocket = &self.pockets[self.turn as usize];
+        for piecetype in POCKET_ORDER.into_iter().filter(|piecetype| pocket.contains(piecetype)) {
+            for pos in 0..64 {
+                let pawn_row = pos / 8 == 0 || pos / 8 == 7;
+                if self.gameboard[pos].is_none() && !(piecetype == PieceType::Pawn && pawn_row) {
+                    moves.push(Move::new_drop(piecetype, pos));
+                }
+            }
+        }
+        moves
     }
 
+    /// Returns None if `_from` or `_to` is not a square name like "e2".
     pub fn make_move(&mut self, _from: &str, _to: &str) -> Option<GameState> {
         
         if self.state != GameState::SetPromotion {
-            let pos = *self.string_to_int.get(_from).unwrap();
-            let newpos = *self.string_to_int.get(_to).unwrap();
+            let pos = *self.string_to_int.get(_from)?;
+            let newpos = *self.string_to_int.get(_to)?;
+            let before = self.undo_record();
             let allowed: bool;
             let is_pawn: bool;
+            let is_king: bool;
+            let is_capture: bool;
+            let mut captured: Option<Piece> = None;
+            let castling = self.castling_move(pos, newpos);
+            let en_passant = self.en_passant_capture(&Move::new(pos, newpos));
             {
                 let board = &mut self.gameboard;
+                is_capture = (board[newpos].is_some() && castling.is_none()) || en_passant.is_some();
                 let piece = board.get_mut(pos).unwrap();
                 allowed = match piece {
-                    Some(piece) if piece.piececolor == self.turn => self
-                        .possible_moves
-                        .get(&pos)
-                        .unwrap()
-                        .iter()
-                        .any(|_move| *_move == newpos),
+                    Some(piece) if piece.piececolor == self.turn => match self.possible_moves.get(&pos) {
+                        Some(moves) => moves.iter().any(|_move| *_move == newpos),
+                        None => false,
+                    },
                     _ => false,
                 };
     
                 is_pawn = match piece {
                     Some(piece) if piece.piecetype == PieceType::Pawn => {
-                        piece.hasmoved = true;
+                        if allowed {
+                            piece.hasmoved = true;
+                        }
                         true
                     }
                     _ => false,
                 };
+                is_king = match piece {
+                    Some(piece) => piece.piecetype == PieceType::King,
+                    None => false,
+                };
                 if allowed {
-                    board.swap(pos, newpos);
-                    board[pos] = None;
+                    match castling {
+                        Some(castling) => {
+                            let king = board[pos].take();
+                            let rook = board[castling.rook].take();
+                            board[castling.king_to] = king;
+                            board[castling.rook_to] = rook;
+                        }
+                        None => {
+                            board.swap(pos, newpos);
+                            captured = match en_passant {
+                                Some(taken) => board[taken].take(),
+                                None => board[pos].take(),
+                            };
+                        }
+                    }
                 }
             }
             let mut state = GameState::InProgress;
             if allowed {
+                self.update_castling(pos, newpos, is_king);
+                self.en_passant = if is_pawn && pos.abs_diff(newpos) == 16 {
+                    Some((pos + newpos) / 2)
+                } else {
+                    None
+                };
+                let _move = Move::new(pos, newpos);
+                self.history.push(_move);
+                if is_pawn || is_capture {
+                    self.halfmove_clock = 0;
+                } else {
+                    self.halfmove_clock += 1;
+                }
+                let variant = Arc::clone(&self.variant);
+                variant.after_move(self, &_move, captured);
+                self.undo_records.push(before.changed(&self.gameboard));
+
+                // the variant may have removed the pawn
+                let is_pawn = match &self.gameboard[newpos] {
+                    Some(piece) => is_pawn && piece.piecetype == PieceType::Pawn,
+                    None => false,
+                };
                 if is_pawn {
                     state = self.check_promotion(newpos);
                 }
@@ -93,13 +339,8 @@ impl Game {
                     self.possible_moves = HashMap::new();
                 }
                 else {
-                    state = self.get_all_possible_moves(self.turn).1;
-                    if self.turn == PieceColor::White {
-                        self.turn = PieceColor::Black;
-                    } else {
-                        self.turn = PieceColor::White;
-                    }
-                    self.possible_moves = self.get_all_possible_moves(self.turn).0;
+                    self.finish_turn();
+                    state = self.state;
                 }
             }
     
@@ -111,43 +352,85 @@ impl Game {
         return Some(self.state);
     }
 
-    pub fn set_promotion(&mut self, _piece: &str) { //Queen = "q", Bishop = "b", Knight = "kn", Rook = "r"
+    pub fn set_promotion(&mut self, _piece: &str) { //Queen = "q", Bishop = "b", Knight = "kn", Rook = "r", King = "k" (Antichess)
+        let typee = self.string_to_piece(_piece);
+        if typee != PieceType::Pawn {
+            self.promote(typee);
+        }
+    }
+
+    fn promote(&mut self, typee: PieceType) {
     
         let mut allowed = false;
         {
-            if self.promotion_pos != None {
+            if self.promotion_pos != None && self.variant.promotion_pieces().contains(&typee) {
                 allowed = true;
             }
         }
 
         if allowed {
-            let typee = self.string_to_piece(_piece);
             let board = &mut self.gameboard;
             let mut piece = board.get_mut(self.promotion_pos.unwrap()).unwrap();
             match piece {
                 Some(piece) => {
                     piece.piecetype = typee;
+                    piece.promoted = true;
                 },
                 None => {},
             }
             self.promotion_pos = None;
-
-            let mut turn = self.turn;
-            let state = self.get_all_possible_moves(turn).1;
-
-            if self.turn == PieceColor::White{
-                
-                self.turn = PieceColor::Black;
-            } else {
-                self.turn = PieceColor::White;
+            if let Some(last) = self.history.last_mut() {
+                last.promotion = Some(typee);
             }
 
+            self.finish_turn();
+        }
+        
+    }
 
-            self.possible_moves = self.get_all_possible_moves(self.turn).0;
+    /// Drops a piece from the pocket of the side to move onto an empty
+    /// square, as in Crazyhouse. Returns None if `_to` is not a square name.
+    pub fn make_drop(&mut self, piecetype: PieceType, _to: &str) -> Option<GameState> {
+        let newpos = *self.string_to_int.get(_to)?;
+        let _move = Move::new_drop(piecetype, newpos);
+        if self.state == GameState::SetPromotion || !self.possible_drops.contains(&_move) {
+            return Some(self.state);
+        }
+        let before = self.undo_record();
+
+        let pocket = &mut self.pockets[self.turn as usize];
+        let index = pocket.iter().position(|piece| *piece == piecetype).unwrap();
+        pocket.remove(index);
+        let mut piece = Piece::new(piecetype, self.turn);
+        // a pawn dropped on its second rank may still move two squares
+        piece.hasmoved = match self.turn {
+            PieceColor::White => newpos / 8 != 6,
+            PieceColor::Black => newpos / 8 != 1,
+        };
+        self.gameboard[newpos] = Some(piece);
+        self.en_passant = None;
 
-            self.state = state;
+        self.history.push(_move);
+        self.undo_records.push(before.changed(&self.gameboard));
+        if piecetype == PieceType::Pawn {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
         }
-        
+        self.finish_turn();
+        Some(self.state)
+    }
+
+    /// The pieces `color` has in hand and can drop, in Crazyhouse.
+    pub fn get_pocket(&self, color: PieceColor) -> &[PieceType] {
+        &self.pockets[color as usize]
+    }
+
+    /// Puts a piece in the pocket of `color`.
+    pub(crate) fn add_to_pocket(&mut self, color: PieceColor, piecetype: PieceType) {
+        let pocket = &mut self.pockets[color as usize];
+        pocket.push(piecetype);
+        pocket.sort_by_key(|piece| POCKET_ORDER.iter().position(|p| p == piece));
     }
 
     fn string_to_piece(&self, _piece: &str) -> PieceType {
@@ -160,6 +443,8 @@ impl Game {
             typee = PieceType::Knight;
         } else if _piece == "r" {
             typee = PieceType::Rook;
+        } else if _piece == "k" {
+            typee = PieceType::King;
         }
         return typee;
     }
@@ -168,6 +453,89 @@ impl Game {
         self.state
     }
 
+    /// How the game ended, None while it is still going on.
+    pub fn get_outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    /// How many times `color` has given check in this game.
+    pub fn get_checks(&self, color: PieceColor) -> u32 {
+        self.checks[color as usize]
+    }
+
+    /// The rules this game is played by.
+    pub fn variant(&self) -> &dyn Variant {
+        self.variant.as_ref()
+    }
+
+    /// All moves made since the game was created, oldest first.
+    pub fn get_history(&self) -> &[Move] {
+        &self.history
+    }
+
+    /// Takes back the last move. A move waiting for `set_promotion` is taken
+    /// back as a whole. Returns the new state, or None if there is no move to
+    /// take back.
+    pub fn undo(&mut self) -> Option<GameState> {
+        let record = self.undo_records.pop()?;
+        self.history.pop();
+        for (pos, piece) in record.squares {
+            self.gameboard[pos] = piece;
+        }
+        self.turn = record.turn;
+        self.promotion_pos = None;
+        self.halfmove_clock = record.halfmove_clock;
+        self.fullmove_number = record.fullmove_number;
+        self.castling = record.castling;
+        self.en_passant = record.en_passant;
+        self.checks = record.checks;
+        self.pockets = record.pockets;
+        self.update_state();
+
+        Some(self.state)
+    }
+
+    /// The position before a move, for `undo`.
+    fn undo_record(&self) -> UndoRecord {
+        UndoRecord {
+            squares: self.gameboard.iter().cloned().enumerate().collect(),
+            turn: self.turn,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            castling: self.castling.clone(),
+            en_passant: self.en_passant,
+            checks: self.checks,
+            pockets: self.pockets.clone(),
+        }
+    }
+
+    /// New game in the position this game started from.
+    fn start_position(&self) -> Game {
+        Self::load_fen(&self.start_fen, self.chess960, Arc::clone(&self.variant)).unwrap()
+    }
+
+    /// Plays a move from the history, including its promotion.
+    /// Returns false if the move is not allowed in the current position.
+    fn replay(&mut self, _move: &Move) -> bool {
+        let played = self.history.len();
+        if let Some(piecetype) = _move.drop {
+            self.make_drop(piecetype, &square_name(_move.to));
+            return self.history.len() > played;
+        }
+        self.make_move(&square_name(_move.from), &square_name(_move.to));
+        if self.history.len() == played {
+            return false;
+        }
+        match _move.promotion {
+            Some(promotion) if self.promotion_pos.is_some() => {
+                self.promote(promotion);
+                self.promotion_pos.is_none()
+            }
+            Some(_) => false,
+            None => true,
+        }
+    }
+
     fn check_game_state(&self) -> GameState {
         GameState::InProgress
     }
@@ -176,149 +544,253 @@ impl Game {
         
     }
 
-    fn possible_moves_king(&self, turn: PieceColor) -> Vec<usize>{
+    /// Castling moves for the king on `kingpos`. The king moves two squares in
+    /// a standard game and onto its own rook in Chess960.
+    fn possible_moves_castling(&self, kingpos: usize, turn: PieceColor) -> Vec<usize> {
         let mut moves: Vec<usize> = Vec::new();
-        
-        let mut kingpos: usize = 0;
-        {
-            while kingpos < 64 {
-                let piece: &Option<Piece> = self.gameboard.get(kingpos).unwrap();
-                match piece {
-                    Some(piece) => {
-                        if piece.piececolor == turn && piece.piecetype == PieceType::King {
-                            break;
-                        }
-                    },
-                    None => {},
-                }
-                kingpos = kingpos + 1;
+        for rook in self.castling.iter() {
+            if *rook / 8 != kingpos / 8 {
+                continue;
+            }
+            let newpos = if self.chess960 {
+                *rook
+            } else if *rook > kingpos {
+                kingpos + 2
+            } else {
+                kingpos - 2
+            };
+            let castling = match self.castling_move(kingpos, newpos) {
+                Some(castling) => castling,
+                None => continue,
+            };
+
+            if self.castling_allowed(&castling, kingpos, turn) {
+                moves.push(newpos);
             }
         }
-        
-        let mut maybe: Vec<usize> = Vec::new();
-        {
-            let distances = self.distances.get(&kingpos).unwrap();
-            let mut direction: usize = 0;
-            while direction < 8 {
-                let distance = distances[direction];
-                if distance > 0 {
-                    let newpos = (kingpos as i16 + self.movements[direction]) as usize;
+        moves
+    }
 
-                    let piece = self.gameboard.get(newpos).unwrap();
-                    match piece {
-                        Some(piece) => {
-                            if piece.piececolor != turn {
-                                maybe.push(newpos);
-                            }
-                        },
-                        None => {
-                            maybe.push(newpos);
-                        },
-                    }
+    /// Tells if the squares the king and rook pass are free and the king is
+    /// not attacked on its way.
+    pub(crate) fn castling_allowed(&self, castling: &Castling, kingpos: usize, turn: PieceColor) -> bool {
+        // Everything between the king, the rook and their new squares
+        // must be empty, apart from the king and rook themselves.
+        let squares = [kingpos, castling.rook, castling.king_to, castling.rook_to];
+        let first = *squares.iter().min().unwrap();
+        let last = *squares.iter().max().unwrap();
+        let blocked = (first..=last).any(|pos| {
+            pos != kingpos && pos != castling.rook && self.gameboard[pos].is_some()
+        });
+        if blocked {
+            return false;
+        }
+
+        // The king may not castle out of, through or into check.
+        let board: [Option<Piece>; 64] = std::array::from_fn(|pos| match pos {
+            _ if pos == kingpos || pos == castling.rook => None,
+            _ => self.gameboard[pos].clone(),
+        });
+        let (first, last) = (cmp::min(kingpos, castling.king_to), cmp::max(kingpos, castling.king_to));
+        let enemy = fen::opposite(turn);
+        !(first..=last).any(|pos| self.square_attacked(&board, pos, enemy))
+    }
+
+    /// Where the king and rook end up if moving the king from `pos` to
+    /// `newpos` is a castling move.
+    fn castling_move(&self, pos: usize, newpos: usize) -> Option<Castling> {
+        let color = match &self.gameboard[pos] {
+            Some(piece) if piece.piecetype == PieceType::King => piece.piececolor,
+            _ => return None,
+        };
+        if pos / 8 != newpos / 8 {
+            return None;
+        }
+        let rook = if self.chess960 {
+            newpos
+        } else if newpos == pos + 2 {
+            pos + 3
+        } else if newpos + 2 == pos {
+            pos - 4
+        } else {
+            return None;
+        };
+        if !self.castling.contains(&rook) {
+            return None;
+        }
+        match &self.gameboard[rook] {
+            Some(piece) if piece.piecetype == PieceType::Rook && piece.piececolor == color => (),
+            _ => return None,
+        }
+        let row = pos / 8 * 8;
+        if rook > pos {
+            Some(Castling { rook, king_to: row + 6, rook_to: row + 5, kingside: true })
+        } else {
+            Some(Castling { rook, king_to: row + 2, rook_to: row + 3, kingside: false })
+        }
+    }
+
+    /// Removes castling rights lost when the side to move moves from `pos`
+    /// to `newpos`.
+    fn update_castling(&mut self, pos: usize, newpos: usize, is_king: bool) {
+        if is_king {
+            let row = match self.turn {
+                PieceColor::White => 7,
+                PieceColor::Black => 0,
+            };
+            self.castling.retain(|rook| *rook / 8 != row);
+        }
+        self.castling.retain(|rook| *rook != pos && *rook != newpos);
+    }
+
+    /// Tells if the side to move is in check.
+    pub fn is_check(&self) -> bool {
+        self.variant.king_attacked(self, &self.gameboard, self.turn)
+    }
+
+    /// Tells if `_move` would leave the king of the side making it attacked.
+    /// Always false for a side without a king.
+    pub fn leaves_king_in_check(&self, _move: &Move) -> bool {
+        let color = match &self.gameboard[_move.from] {
+            _ if _move.drop.is_some() => self.turn,
+            Some(piece) => piece.piececolor,
+            None => return false,
+        };
+        self.variant.king_attacked(self, &self.board_after(_move), color)
+    }
+
+    /// The board after moving a piece, including the rook of a castling move
+    /// but without any other rules of the variant. Drops are made by the side
+    /// to move.
+    pub fn board_after(&self, _move: &Move) -> Vec<Option<Piece>> {
+        let mut board = self.gameboard.clone();
+        if let Some(piecetype) = _move.drop {
+            board[_move.to] = Some(Piece::new(piecetype, self.turn));
+            return board;
+        }
+        match self.castling_move(_move.from, _move.to) {
+            Some(castling) => {
+                let king = board[_move.from].take();
+                let rook = board[castling.rook].take();
+                board[castling.king_to] = king;
+                board[castling.rook_to] = rook;
+            }
+            None => {
+                if let Some(taken) = self.en_passant_capture(_move) {
+                    board[taken] = None;
+                }
+                board[_move.to] = board[_move.from].take();
+                if let (Some(piece), Some(promotion)) = (&mut board[_move.to], _move.promotion) {
+                    piece.piecetype = promotion;
                 }
-                direction = direction + 1;
             }
         }
+        board
+    }
 
+    /// The square of the pawn taken if `_move` is an en passant capture.
+    pub(crate) fn en_passant_capture(&self, _move: &Move) -> Option<usize> {
+        if self.en_passant != Some(_move.to) || _move.drop.is_some() || _move.from % 8 == _move.to % 8 {
+            return None;
+        }
+        let taken = _move.from / 8 * 8 + _move.to % 8;
+        match (&self.gameboard[_move.from], &self.gameboard[taken]) {
+            (Some(piece), Some(pawn))
+                if piece.piecetype == PieceType::Pawn
+                    && pawn.piecetype == PieceType::Pawn
+                    && piece.piececolor != pawn.piececolor =>
+            {
+                Some(taken)
+            }
+            _ => None,
+        }
+    }
 
-        for pos in maybe.iter(){// loop alla öppna positioner runt kungen
-            let mut allowed = true;
-            let distances = self.distances.get(pos).unwrap();
-            let mut direction: usize = 0;
-            while direction < 8 {// titta alla håll från nya positionen
-                let mut newpos = *pos;
-                let distance = distances[direction];
-                if distance > 0 {
-                    let mut range = 0;
-                    while range < distance {
-                        newpos = (newpos as i16 + self.movements[direction]) as usize;
+    /// Tells if a piece of color `by` attacks `pos` on `board`.
+    fn square_attacked(&self, board: &[Option<Piece>], pos: usize, by: PieceColor) -> bool {
+        let mut attacked = false;
+        self.visit_attackers(board, pos, by, |_| {
+            attacked = true;
+            false
+        });
+        attacked
+    }
 
-                        let piece = self.gameboard.get(newpos).unwrap();
-                        match piece {
-                            Some(piece) => {
-                                if piece.piececolor != turn{
-                                    if (direction + 2) % 2 == 0 {
-                                        if piece.piecetype == PieceType::Rook || piece.piecetype == PieceType::Queen {
-                                            allowed = false;
-                                        }
-                                    }
-                                    else{
-                                        if piece.piecetype == PieceType::Bishop || piece.piecetype == PieceType::Queen {
-                                            allowed = false;
-                                        } else if piece.piecetype == PieceType::Pawn && range == 0 {
-                                            if piece.piececolor == PieceColor::White {
-                                                if direction == 1 || direction == 7 {
-                                                    allowed = false;
-                                                }
-                                            } else {
-                                                if direction == 3 || direction == 5 {
-                                                    allowed == false;
-                                                }
-                                            }
-                                        }
+    /// The squares of the pieces of color `by` that attack `pos` on `board`.
+    pub(crate) fn attackers(&self, board: &[Option<Piece>], pos: usize, by: PieceColor) -> Vec<usize> {
+        let mut attackers: Vec<usize> = Vec::new();
+        self.visit_attackers(board, pos, by, |attacker| {
+            attackers.push(attacker);
+            true
+        });
+        attackers
+    }
+
+    /// Calls `visit` with the square of each piece of color `by` that attacks
+    /// `pos` on `board`, until it returns false.
+    pub(crate) fn visit_attackers(
+        &self,
+        board: &[Option<Piece>],
+        pos: usize,
+        by: PieceColor,
+        mut visit: impl FnMut(usize) -> bool,
+    ) {
+        let distances = self.distances.get(&pos).unwrap();
+        let mut direction: usize = 0;
+        while direction < 8 {
+            let mut newpos = pos;
+            let mut range = 0;
+            while range < distances[direction] {
+                newpos = (newpos as i16 + self.movements[direction]) as usize;
+                if let Some(piece) = &board[newpos] {
+                    if piece.piececolor == by {
+                        let attacks = match piece.piecetype {
+                            PieceType::Queen => true,
+                            PieceType::Rook => direction.is_multiple_of(2),
+                            PieceType::Bishop => direction % 2 == 1,
+                            PieceType::King => range == 0,
+                            // white pawns attack upwards, so they are found below
+                            PieceType::Pawn => {
+                                range == 0
+                                    && match by {
+                                        PieceColor::White => direction == 3 || direction == 5,
+                                        PieceColor::Black => direction == 1 || direction == 7,
                                     }
-                                }
-                                break;
-                            },
-                            None => {
-                                
-                            },
+                            }
+                            PieceType::Knight => false,
+                        };
+                        if attacks && !visit(newpos) {
+                            return;
                         }
-                        range = range + 1;
                     }
+                    break;
                 }
-                direction = direction + 1;
+                range += 1;
             }
-            let mut first_kn_dir: usize = 0;
-            if allowed {
-                while first_kn_dir < 8 { //Titta om en häst ser positionen
-                    if self.distances.get(&pos).unwrap()[first_kn_dir] >= 2 {
-                        let first_kn_pos = (*pos as i16 + (self.movements[first_kn_dir] * 2)) as usize;
-                        let mut start: usize = 0;
-                        let mut end: usize = 4;
-                        if (((first_kn_dir + 2) / 2) + 1) % 2 == 0 {
-                        start = start + 2;
-                        end = end + 2;
-                        }
-                        let mut second_kn_dir = start;
-                        while second_kn_dir <= end {
-                            if self.distances.get(&first_kn_pos).unwrap()[second_kn_dir] >= 1 {
-                                let second_kn_pos = (first_kn_pos as i16 + self.movements[second_kn_dir]) as usize;
-                                let piece = self.gameboard.get(second_kn_pos).unwrap();
-                                match piece {
-                                    Some(piece) => {
-                                        if piece.piececolor != turn && piece.piecetype == PieceType::Knight {
-                                            allowed = false;
-                                        }
-                                    },
-                                    None => {
-                                        
-                                    },
-                                }
-                            }
-                            second_kn_dir = second_kn_dir + 4;
-                        }
-                    
+            direction += 1;
+        }
+
+        let (row, col) = ((pos / 8) as i16, (pos % 8) as i16);
+        for (up, right) in [(-2, -1), (-2, 1), (-1, 2), (1, 2), (2, 1), (2, -1), (1, -2), (-1, -2)] {
+            let (r, c) = (row + up, col + right);
+            if (0..8).contains(&r) && (0..8).contains(&c) {
+                if let Some(piece) = &board[(r * 8 + c) as usize] {
+                    if piece.piececolor == by && piece.piecetype == PieceType::Knight && !visit((r * 8 + c) as usize) {
+                        return;
                     }
-                    first_kn_dir = first_kn_dir + 2;
                 }
             }
-            if allowed {
-                moves.push(*pos);
-            }
         }
-        moves
     }
 
-
     pub fn get_possible_moves(&self, _postion: &str) -> Option<Vec<String>> {
-        let pos = *self.string_to_int.get(_postion).unwrap();
+        let pos = *self.string_to_int.get(_postion)?;
         let piece: &Option<Piece> = self.gameboard.get(pos).unwrap();
         match piece {
             Some(piece) => {
                 if self.turn == piece.piececolor {
                     let mut moves: Vec<String> = Vec::new();
-                    for _move in self.possible_moves.get(&pos).unwrap().iter() {
+                    for _move in self.possible_moves.get(&pos)?.iter() {
                         moves.push(self.int_to_string.get(_move).unwrap().to_owned());
                     }
                     return Some(moves);
@@ -330,92 +802,46 @@ impl Game {
         }
     }
 
+    /// All moves the side to move can make, drops last. A pawn move to the
+    /// last rank is listed once, the piece is chosen afterwards with
+    /// `set_promotion`.
+    pub fn get_legal_moves(&self) -> Vec<Move> {
+        let mut moves: Vec<Move> = Vec::new();
+        let mut positions: Vec<&usize> = self.possible_moves.keys().collect();
+        positions.sort();
+        for pos in positions {
+            for newpos in self.possible_moves.get(pos).unwrap().iter() {
+                moves.push(Move::new(*pos, *newpos));
+            }
+        }
+        moves.extend(self.possible_drops.iter().copied());
+        moves
+    }
+
+    /// Moves the pieces of `turn` can make, before the variant decides which
+    /// of them are allowed. Also tells if one of them attacks the enemy king.
     fn get_all_possible_moves(&self, turn: PieceColor) -> (HashMap<usize, Vec<usize>>, GameState) {
         let mut map: HashMap<usize, Vec<usize>> = HashMap::new();
-        let mut allowed_direction: HashMap<usize, usize> = HashMap::new(); //Blocking and axis
         let mut state = GameState::InProgress;
-        let mut posi = 0;
-        while posi < 64 {//Is piece blocking check
-            let piece: &Option<Piece> = self.gameboard.get(posi).unwrap();
-            match piece {
-                Some(piece) => {
-                    if piece.piececolor != turn {
-                        let mut respons: Option<(usize, usize)> = None;
-                        match piece.piecetype {
-                            PieceType::King => (),
-                            PieceType::Queen => {
-                                respons = self.blocking_check(posi, 0, 1, turn);
-                            }
-                            PieceType::Bishop => {
-                                respons = self.blocking_check(posi, 1, 2, turn);
-                            }
-                            PieceType::Knight => (),
-                            PieceType::Rook => {
-                                respons = self.blocking_check(posi, 0, 2, turn);
-                            }
-                            PieceType::Pawn => (),
-                        }
-                        match respons {
-                            Some(respons) => {
-                                allowed_direction.insert(respons.0, respons.1);
-                            }
-                            None => (),
-                        }
-                    }
-                }
-                None => (),
-            }
-            posi = posi + 1;
-        }
         
         let mut position = 0;
         while position < 64 {
             let piece: &Option<Piece> = self.gameboard.get(position).unwrap();
             match piece {
                 Some(piece) => {
-                    if piece.piececolor == self.turn {
-                        let allowed = allowed_direction.get(&position);
-
-                        let mut response: (Vec<usize>, GameState) = (Vec::new(), GameState::InProgress);
-                    
-                        match allowed {
-                            Some(allowed) => match piece.piecetype {
-                                PieceType::King => (),
-                                PieceType::Queen => {
-                                    response = self.possible_moves(position, 0, 1, turn, false, *allowed,);
-                                }
-                                PieceType::Bishop => {
-                                    response = self.possible_moves(position, 1, 2, turn, false, *allowed,);
-                                }
-                                PieceType::Knight => (),
-                                PieceType::Rook => {
-                                    response = self.possible_moves(position, 0, 2, turn, false, *allowed,);
-                                }
-                                PieceType::Pawn => {
-                                    response = self.possible_moves_pawn(position, turn, piece.hasmoved, *allowed,);
-                                }
-                            },
-                            None => match piece.piecetype {
-                                PieceType::King => {
-                                    response.0 = self.possible_moves_king(turn);
-                                }
-                                PieceType::Queen => {
-                                    response = self.possible_moves(position, 0, 1, turn, false, 100,);
-                                }
-                                PieceType::Bishop => {
-                                    response = self.possible_moves(position, 1, 2, turn, false, 100,);
-                                }
-                                PieceType::Knight => {
-                                    response = self.possible_moves_knight(position, turn);
-                                }
-                                PieceType::Rook => {
-                                    response = self.possible_moves(position, 0, 2, turn, false, 100,);
-                                }
-                                PieceType::Pawn => {
-                                    response = self.possible_moves_pawn(position, turn, piece.hasmoved, 100,);
-                                }
-                            },
-                        }
+                    if piece.piececolor == turn {
+                        let response: (Vec<usize>, GameState) = match piece.piecetype {
+                            PieceType::King => {
+                                let mut response = self.possible_moves(position, 0, 1, turn, true, 100,);
+                                response.0.append(&mut self.possible_moves_castling(position, turn));
+                                response
+                            }
+                            PieceType::Queen => self.possible_moves(position, 0, 1, turn, false, 100,),
+                            PieceType::Bishop => self.possible_moves(position, 1, 2, turn, false, 100,),
+                            PieceType::Knight => self.possible_moves_knight(position, turn),
+                            PieceType::Rook => self.possible_moves(position, 0, 2, turn, false, 100,),
+                            PieceType::Pawn => self.possible_moves_pawn(position, turn, piece.hasmoved, 100,),
+                        };
                         map.insert(position, response.0);
                         if response.1 == GameState::Check{
                             state = response.1;
@@ -599,7 +1025,7 @@ impl Game {
                                 newpos = (newpos as i16 + (direction * reverse)) as usize;
                                 let piece  = self.gameboard.get(newpos).unwrap();
                                 match piece {
-                                    Some(_piece) => (),
+                                    Some(_piece) => break,
                                     None => {
                                         moves.push(newpos);
                                     }
@@ -619,7 +1045,11 @@ impl Game {
                                     }
                                 }
                             }
-                            None => {}
+                            None => {
+                                if self.en_passant_capture(&Move::new(position, newpos)).is_some() {
+                                    moves.push(newpos);
+                                }
+                            }
                         }
                     }
                 }
@@ -630,57 +1060,6 @@ impl Game {
         (moves, state)
     }
 
-    fn blocking_check(
-        &self,
-        position: usize,
-        start: usize,
-        add: usize,
-        turn: PieceColor,
-    ) -> Option<(usize, usize)> {
-        let mut direction: usize = start;
-        while direction < 8 {
-            let mut newpos: usize = position;
-            let mut blocking: Option<usize> = None;
-            let mut range: usize = 0;
-
-            let _x: i16 = position as i16;
-            let stop: usize = self.distances.get(&position).unwrap()[direction] as usize;
-
-            while range < stop {
-                newpos = ((newpos as i16) + self.movements[direction]) as usize;
-                let piece: &Option<Piece> = self.gameboard.get(newpos).unwrap();
-
-                match piece {
-                    Some(piece) => {
-                        if piece.piececolor != turn {
-                            break;
-                        }
-
-                        if piece.piecetype != PieceType::King {
-                            match blocking {
-                                Some(_blocking) => break,
-                                None => {
-                                    blocking = Some(newpos);
-                                }
-                            }
-                        } else {
-                            match blocking {
-                                Some(_blocking) => return Some((_blocking, direction)),
-                                None => {}
-                            }
-                        }
-                    }
-                    None => {}
-                }
-
-                range = range + 1;
-            }
-
-            direction = direction + add;
-        }
-        return None;
-    }
-
     fn blocking_check_pawn(&self, position: usize, turn: PieceColor) {
         if turn == PieceColor::Black { // Vit bonde
         } else { // Svart bonde
@@ -798,11 +1177,36 @@ impl Game {
     }
 }
 
+/// What a move changed, so that `undo` can put it back without playing the
+/// game again from the start.
 #[derive(Clone)]
+struct UndoRecord {
+    squares: Vec<(usize, Option<Piece>)>, // as they were before the move
+    turn: PieceColor,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    castling: Vec<usize>,
+    en_passant: Option<usize>,
+    checks: [u32; 2],
+    pockets: [Vec<PieceType>; 2],
+}
+
+impl UndoRecord {
+    /// Keeps only the squares that are different on `board`.
+    fn changed(mut self, board: &[Option<Piece>]) -> UndoRecord {
+        self.squares.retain(|(pos, piece)| board[*pos] != *piece);
+        self
+    }
+}
+
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Piece {
     pub piecetype: PieceType,
     pub piececolor: PieceColor,
     hasmoved: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    promoted: bool, // turns back into a pawn when captured in Crazyhouse
 }
 
 impl Piece {
@@ -811,6 +1215,7 @@ impl Piece {
             piecetype,
             piececolor,
             hasmoved: false,
+            promoted: false,
         }
     }
 
@@ -825,7 +1230,133 @@ impl Piece {
 
 }
 
+/// Squares of a castling move. `rook` is where the rook stands before.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Castling {
+    rook: usize,
+    king_to: usize,
+    rook_to: usize,
+    kingside: bool,
+}
+
+/// A move from one square to another. Squares use the same index as
+/// `gameboard`, 0 = a8 and 63 = h1. A drop puts the piece `drop` from the
+/// pocket on `to`, and has `from` equal to `to`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Move {
+    pub from: usize,
+    pub to: usize,
+    pub promotion: Option<PieceType>,
+    pub drop: Option<PieceType>,
+}
+
+impl Move {
+    pub fn new(from: usize, to: usize) -> Move {
+        Move {
+            from,
+            to,
+            promotion: None,
+            drop: None,
+        }
+    }
+
+    pub fn new_drop(piecetype: PieceType, to: usize) -> Move {
+        Move {
+            from: to,
+            to,
+            promotion: None,
+            drop: Some(piecetype),
+        }
+    }
+
+    /// Parses coordinate notation, e.g. "e2e4", "e7e8q" or the drop "N@f3".
+    pub fn from_uci(_move: &str) -> Option<Move> {
+        if !_move.is_ascii() {
+            return None;
+        }
+        if let Some((piece, square)) = _move.split_once('@') {
+            let piecetype = match piece {
+                "Q" => PieceType::Queen,
+                "R" => PieceType::Rook,
+                "B" => PieceType::Bishop,
+                "N" => PieceType::Knight,
+                "P" => PieceType::Pawn,
+                _ => return None,
+            };
+            return Some(Move::new_drop(piecetype, square_index(square)?));
+        }
+        if _move.len() != 4 && _move.len() != 5 {
+            return None;
+        }
+        let from = square_index(&_move[0..2])?;
+        let to = square_index(&_move[2..4])?;
+        let promotion = match _move.get(4..5) {
+            None => None,
+            Some("q") => Some(PieceType::Queen),
+            Some("r") => Some(PieceType::Rook),
+            Some("b") => Some(PieceType::Bishop),
+            Some("n") => Some(PieceType::Knight),
+            Some("k") => Some(PieceType::King),
+            Some(_) => return None,
+        };
+        Some(Move {
+            from,
+            to,
+            promotion,
+            drop: None,
+        })
+    }
+}
+
+/// Output example: "e2e4", "e7e8q", "N@f3"
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(piecetype) = self.drop {
+            let piece = Piece::new(piecetype, PieceColor::White);
+            return write!(f, "{}@{}", fen::piece_to_char(&piece), square_name(self.to));
+        }
+        write!(f, "{}{}", square_name(self.from), square_name(self.to))?;
+        match self.promotion {
+            Some(PieceType::Queen) => write!(f, "q"),
+            Some(PieceType::Rook) => write!(f, "r"),
+            Some(PieceType::Bishop) => write!(f, "b"),
+            Some(PieceType::Knight) => write!(f, "n"),
+            Some(PieceType::King) => write!(f, "k"),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Order pieces are listed in a pocket.
+const POCKET_ORDER: [PieceType; 6] = [
+    PieceType::King,
+    PieceType::Queen,
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Knight,
+    PieceType::Pawn,
+];
+
+/// "a8" -> 0, "h1" -> 63
+pub(crate) fn square_index(square: &str) -> Option<usize> {
+    let bytes = square.as_bytes();
+    if bytes.len() != 2 || !(b'a'..=b'h').contains(&bytes[0]) || !(b'1'..=b'8').contains(&bytes[1]) {
+        return None;
+    }
+    Some((b'8' - bytes[1]) as usize * 8 + (bytes[0] - b'a') as usize)
+}
+
+/// 0 -> "a8", 63 -> "h1"
+pub(crate) fn square_name(pos: usize) -> String {
+    let mut s = String::new();
+    s.push((b'a' + (pos % 8) as u8) as char);
+    s.push((b'8' - (pos / 8) as u8) as char);
+    s
+}
+
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "python", pyo3::pyclass(eq, eq_int, from_py_object, module = "erikrub_chess_lib"))]
 pub enum PieceType {
     King,
     Queen,
@@ -836,6 +1367,9 @@ pub enum PieceType {
 }
 
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "wasm", wasm_bindgen::prelude::wasm_bindgen)]
+#[cfg_attr(feature = "python", pyo3::pyclass(eq, eq_int, from_py_object, module = "erikrub_chess_lib"))]
 pub enum PieceColor {
     White,
     Black,
@@ -901,6 +1435,7 @@ mod tests {
 
     use super::Game;
     use super::GameState;
+    use super::Move;
 
     // check test framework
     #[test]
@@ -984,4 +1519,89 @@ mod tests {
 
         //assert_eq!(game.get_game_state(), GameState::InProgress);
     }
+
+    #[test]
+    fn undo_restores_previous_position() {
+        let mut game = Game::new();
+        let start = game.to_fen();
+        game.make_move("e2", "e4");
+        let after_e4 = game.to_fen();
+        game.make_move("e7", "e5");
+
+        assert_eq!(game.undo(), Some(GameState::InProgress));
+        assert_eq!(game.to_fen(), after_e4);
+        assert_eq!(game.get_possible_moves("e7"), Some(vec!["e6".to_string(), "e5".to_string()]));
+        game.undo();
+        assert_eq!(game.to_fen(), start);
+        assert_eq!(game.undo(), None);
+    }
+
+    #[test]
+    fn undo_whole_game() {
+        let mut game = Game::from_fen("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1").unwrap();
+        let mut positions = Vec::new();
+        for (from, to) in [("e5", "d6"), ("e8", "g8"), ("b7", "a8"), ("f8", "a8"), ("a1", "a8"), ("g8", "g7")] {
+            positions.push((game.to_fen(), game.get_legal_moves()));
+            game.make_move(from, to);
+            if game.get_game_state() == GameState::SetPromotion {
+                game.set_promotion("q");
+            }
+        }
+        assert_eq!(game.get_history().len(), 6);
+        while let Some((fen, moves)) = positions.pop() {
+            game.undo();
+            assert_eq!(game.to_fen(), fen);
+            assert_eq!(game.get_legal_moves(), moves);
+        }
+        assert_eq!(game.undo(), None);
+    }
+
+    #[test]
+    fn undo_pending_promotion() {
+        let mut game = Game::from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        game.make_move("b7", "b8");
+        assert_eq!(game.get_game_state(), GameState::SetPromotion);
+        game.undo();
+        assert_eq!(game.get_game_state(), GameState::InProgress);
+        assert_eq!(game.to_fen(), "4k3/1P6/8/8/8/8/8/4K3 w - - 0 1");
+    }
+
+    #[test]
+    fn castling() {
+        let mut game = Game::new();
+        for (from, to) in [("e2", "e4"), ("e7", "e5"), ("g1", "f3"), ("b8", "c6"), ("f1", "c4"), ("f8", "c5")] {
+            game.make_move(from, to);
+        }
+        assert!(game.get_possible_moves("e1").unwrap().contains(&"g1".to_string()));
+        assert_eq!(game.move_to_san(&Move::from_uci("e1g1").unwrap()), Some("O-O".to_string()));
+
+        game.make_move("e1", "g1");
+        assert_eq!(game.to_fen(), "r1bqk1nr/pppp1ppp/2n5/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQ1RK1 b kq - 5 4");
+        game.undo();
+        game.make_move("h1", "g1");
+        game.make_move("g8", "f6");
+        game.make_move("g1", "h1");
+        game.make_move("f6", "g8");
+        assert!(!game.get_possible_moves("e1").unwrap().contains(&"g1".to_string()));
+        assert_eq!(game.to_fen(), "r1bqk1nr/pppp1ppp/2n5/2b1p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w Qkq - 8 6");
+    }
+
+    #[test]
+    fn en_passant() {
+        let mut game = Game::new();
+        for (from, to) in [("e2", "e4"), ("a7", "a6"), ("e4", "e5"), ("d7", "d5")] {
+            game.make_move(from, to);
+        }
+        assert_eq!(game.to_fen(), "rnbqkbnr/1pp1pppp/p7/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3");
+        let _move = Move::from_uci("e5d6").unwrap();
+        assert_eq!(game.move_to_san(&_move), Some("exd6".to_string()));
+
+        game.make_move("e5", "d6");
+        assert!(game.gameboard[27].is_none());
+        assert_eq!(game.to_fen(), "rnbqkbnr/1pp1pppp/p2P4/8/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 3");
+        game.undo();
+        game.make_move("h2", "h3");
+        game.make_move("a6", "a5");
+        assert!(!game.get_possible_moves("e5").unwrap().contains(&"d6".to_string()));
+    }
 }
\ No newline at end of file
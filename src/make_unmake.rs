@@ -0,0 +1,247 @@
+use crate::movegen::MoveList;
+use crate::{Castling, Game, Move, Piece, PieceType};
+
+/// What `make_move_unchecked` changed, so that `unmake_move` can put it back.
+#[derive(Clone)]
+pub struct UndoInfo {
+    moved: Option<Piece>,
+    captured: Option<Piece>,
+    captured_pos: usize,
+    castled: Option<Castling>,
+    castling: [Option<usize>; 4],
+    en_passant: Option<usize>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+}
+
+impl Game {
+    /// Plays a move without checking that it is legal and without working
+    /// out the legal moves, state and outcome of the new position, so that
+    /// nothing is allocated. Meant for search in standard chess and
+    /// Chess960: use `generate_moves` and `is_check` in the new position,
+    /// and take the move back with `unmake_move` before using the game in
+    /// any other way. The history is left as it was, and the rules a variant
+    /// adds after a move are not applied. A pawn reaching the last rank
+    /// without a promotion piece becomes a queen.
+    pub fn make_move_unchecked(&mut self, _move: &Move) -> UndoInfo {
+        let mut undo = self.undo_info();
+        undo.moved = self.gameboard[_move.from].clone();
+        undo.captured_pos = _move.to;
+
+        let (is_pawn, is_king) = match &undo.moved {
+            Some(piece) => (piece.piecetype == PieceType::Pawn, piece.piecetype == PieceType::King),
+            None => (false, false),
+        };
+        let en_passant = self.en_passant_capture(_move);
+        let castling = self.castling_move(_move.from, _move.to);
+        let board = &mut self.gameboard;
+        match castling {
+            Some(castling) => {
+                let king = board[_move.from].take();
+                let rook = board[castling.rook].take();
+                board[castling.king_to] = king;
+                board[castling.rook_to] = rook;
+                undo.castled = Some(castling);
+            }
+            None => {
+                if let Some(taken) = en_passant {
+                    undo.captured_pos = taken;
+                }
+                undo.captured = board[undo.captured_pos].take();
+                board[_move.to] = board[_move.from].take();
+                if let Some(piece) = &mut board[_move.to] {
+                    if is_pawn {
+                        piece.hasmoved = true;
+                        if _move.to / 8 == 0 || _move.to / 8 == 7 {
+                            piece.piecetype = _move.promotion.unwrap_or(PieceType::Queen);
+                            piece.promoted = true;
+                        }
+                    }
+                }
+            }
+        }
+
+        self.update_castling(_move.from, _move.to, is_king);
+        self.en_passant = if is_pawn && _move.from.abs_diff(_move.to) == 16 {
+            Some((_move.from + _move.to) / 2)
+        } else {
+            None
+        };
+        if is_pawn || undo.captured.is_some() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        self.pass_turn();
+        undo
+    }
+
+    /// Takes back a move made with `make_move_unchecked`, which must be the
+    /// last one made with it, and puts the game back exactly as it was.
+    pub fn unmake_move(&mut self, _move: &Move, undo: UndoInfo) {
+        self.restore(&undo);
+        let board = &mut self.gameboard;
+        match undo.castled {
+            Some(castling) => {
+                let rook = board[castling.rook_to].take();
+                board[castling.king_to] = None;
+                board[castling.rook] = rook;
+                board[_move.from] = undo.moved;
+            }
+            None => {
+                board[_move.to] = None;
+                board[undo.captured_pos] = undo.captured;
+                board[_move.from] = undo.moved;
+            }
+        }
+    }
+
+    /// Passes the turn without moving, as null move pruning does. The en
+    /// passant square is cleared, so `polyglot_key` is that of the other side
+    /// to move. Like `make_move_unchecked` nothing else is worked out. None,
+    /// and nothing changes, if the side to move is in check or has a
+    /// promotion to choose.
+    pub fn make_null_move(&mut self) -> Option<UndoInfo> {
+        if self.promotion_pos.is_some() || self.is_check() {
+            return None;
+        }
+        let undo = self.undo_info();
+        self.en_passant = None;
+        self.halfmove_clock += 1;
+        self.pass_turn();
+        Some(undo)
+    }
+
+    /// Takes back a null move made with `make_null_move`.
+    pub fn unmake_null_move(&mut self, undo: UndoInfo) {
+        self.restore(&undo);
+    }
+
+    /// The state a move changes apart from the board.
+    fn undo_info(&self) -> UndoInfo {
+        let mut castling = [None; 4];
+        for (saved, rook) in castling.iter_mut().zip(self.castling.iter()) {
+            *saved = Some(*rook);
+        }
+        UndoInfo {
+            moved: None,
+            captured: None,
+            captured_pos: 0,
+            castled: None,
+            castling,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+        }
+    }
+
+    /// Puts back the state saved by `undo_info` and gives the turn back.
+    fn restore(&mut self, undo: &UndoInfo) {
+        self.castling.clear();
+        self.castling.extend(undo.castling.iter().flatten());
+        self.en_passant = undo.en_passant;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.fullmove_number = undo.fullmove_number;
+        self.turn = crate::fen::opposite(self.turn);
+    }
+
+    /// Counts the positions reached after `depth` moves, a move for each
+    /// promotion piece. Used to test move generation.
+    pub fn perft(&mut self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let mut moves = MoveList::new();
+        self.generate_moves(&mut moves);
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+        let mut nodes = 0;
+        for _move in &moves {
+            let undo = self.make_move_unchecked(_move);
+            nodes += self.perft(depth - 1);
+            self.unmake_move(_move, undo);
+        }
+        nodes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::movegen::MoveList;
+    use crate::{Game, Move};
+
+    #[test]
+    fn perft() {
+        let mut game = Game::new();
+        assert_eq!(game.perft(3), 8902);
+        let mut game = Game::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        assert_eq!(game.perft(2), 2039);
+        let mut game = Game::from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1").unwrap();
+        assert_eq!(game.perft(3), 2812);
+        let mut game = Game::from_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1").unwrap();
+        assert_eq!(game.perft(3), 9467);
+        let mut game = Game::from_fen("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8").unwrap();
+        assert_eq!(game.perft(2), 1486);
+    }
+
+    #[test]
+    fn unmake_restores_position() {
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 b kq - 0 1",
+        ];
+        for fen in fens {
+            let mut game = Game::from_fen(fen).unwrap();
+            let mut moves = MoveList::new();
+            game.generate_moves(&mut moves);
+            for _move in &moves {
+                let undo = game.make_move_unchecked(_move);
+                game.unmake_move(_move, undo);
+                assert_eq!(game.to_fen(), fen, "{}", _move);
+            }
+        }
+        // Chess960 castling with the king onto its rook's square
+        let fen = "1r2k1r1/8/8/8/8/8/8/1R2K1R1 w GBgb - 0 1";
+        let mut game = Game::from_fen_chess960(fen).unwrap();
+        let _move = Move::from_uci("e1b1").unwrap();
+        let undo = game.make_move_unchecked(&_move);
+        assert_eq!(game.to_shredder_fen(), "1r2k1r1/8/8/8/8/8/8/2KR2R1 b gb - 1 1");
+        game.unmake_move(&_move, undo);
+        assert_eq!(game.to_shredder_fen(), fen);
+    }
+
+    #[test]
+    fn null_move() {
+        let fen = "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3";
+        let mut game = Game::from_fen(fen).unwrap();
+        let key = game.polyglot_key();
+        let undo = game.make_null_move().unwrap();
+        assert_eq!(game.to_fen(), "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR b KQkq - 1 3");
+        assert_ne!(game.polyglot_key(), key);
+        let mut moves = MoveList::new();
+        game.generate_moves(&mut moves);
+        assert!(moves.contains(&Move::from_uci("e8f7").unwrap()));
+        game.unmake_null_move(undo);
+        assert_eq!(game.to_fen(), fen);
+        assert_eq!(game.polyglot_key(), key);
+
+        // not allowed in check
+        let mut game = Game::from_fen("4k3/8/8/8/1b6/8/8/4K3 w - - 0 1").unwrap();
+        assert!(game.make_null_move().is_none());
+        assert_eq!(game.turn, crate::PieceColor::White);
+    }
+
+    #[test]
+    fn same_as_make_move() {
+        let mut game = Game::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        let mut played = game.clone();
+        for uci in ["e1g1", "a6e2", "d5e6"] {
+            let _move = Move::from_uci(uci).unwrap();
+            game.make_move_unchecked(&_move);
+            assert!(played.replay(&_move));
+            assert_eq!(game.to_fen(), played.to_fen());
+        }
+    }
+}
@@ -0,0 +1,272 @@
+use crate::fen::opposite;
+use crate::make_unmake::UndoInfo;
+use crate::movegen::MoveList;
+use crate::variant::is_capture;
+use crate::{Game, Move, Outcome, PieceType};
+
+/// How to take back a move played by the mate search.
+enum Played {
+    Unmake(UndoInfo),
+    Restore(Box<Game>),
+}
+
+impl Game {
+    /// The shortest forced checkmate for the side to move, in at most
+    /// `max_depth` of its moves, whatever the other side does. The line has
+    /// the mating side's moves and the longest defences in turn and ends with
+    /// mate. None if there is no such mate.
+    pub fn find_mate(&self, max_depth: usize) -> Option<Vec<Move>> {
+        let mut game = self.clone();
+        let depth = game.mate_distance(max_depth)?;
+        let _move = *game.mating_moves_within(depth).first()?;
+        game.mate_line(&_move, depth)
+    }
+
+    /// The moves that force checkmate in at most `max_depth` moves.
+    pub fn mating_moves(&self, max_depth: usize) -> Vec<Move> {
+        self.clone().mating_moves_within(max_depth)
+    }
+
+    /// Tells if a mate in `max_depth` puzzle has a single solution: only one
+    /// move forces mate in time, and after every defence only one move mates
+    /// fastest. The last move may be any mate.
+    pub fn has_unique_mate(&self, max_depth: usize) -> bool {
+        let mut game = self.clone();
+        match game.mating_moves_within(max_depth).as_slice() {
+            [_move] => game.with_move(_move, |game| game.unique_after(max_depth - 1)).unwrap_or(false),
+            _ => false,
+        }
+    }
+
+    /// The legal moves, with a move for each piece a pawn can promote to.
+    pub(crate) fn moves_with_promotions(&self) -> Vec<Move> {
+        let mut moves: Vec<Move> = Vec::new();
+        for _move in self.get_legal_moves() {
+            let promotes = _move.drop.is_none()
+                && (_move.to / 8 == 0 || _move.to / 8 == 7)
+                && matches!(&self.gameboard[_move.from], Some(piece) if piece.piecetype == PieceType::Pawn);
+            if promotes {
+                for piecetype in self.variant.promotion_pieces() {
+                    moves.push(Move {
+                        promotion: Some(*piecetype),
+                        .._move
+                    });
+                }
+            } else {
+                moves.push(_move);
+            }
+        }
+        moves
+    }
+
+    /// The line of the fastest mate that starts with `_move`, in at most
+    /// `max_depth` moves, with the longest defences. None if `_move` does not
+    /// force mate in time.
+    pub(crate) fn mate_line_from(&self, _move: &Move, max_depth: usize) -> Option<Vec<Move>> {
+        let mut game = self.clone();
+        let depth = (1..=max_depth).find(|depth| game.mates_with(_move, *depth))?;
+        game.mate_line(_move, depth)
+    }
+
+    /// Tells if the side to move has been checkmated.
+    pub(crate) fn is_checkmate(&self) -> bool {
+        self.outcome == Some(Outcome::win(opposite(self.turn), "checkmate"))
+    }
+
+    /// The fewest moves the side to move needs to force mate, if it is at
+    /// most `max_depth`.
+    fn mate_distance(&mut self, max_depth: usize) -> Option<usize> {
+        (1..=max_depth).find(|depth| self.can_mate(*depth))
+    }
+
+    /// Tells if the side to move can force mate in at most `depth` moves.
+    fn can_mate(&mut self, depth: usize) -> bool {
+        let mut moves = MoveList::new();
+        self.generate_moves(&mut moves);
+        moves.as_slice().iter().any(|_move| self.mates_with(_move, depth))
+    }
+
+    /// Tells if `_move` forces mate in at most `depth` moves.
+    fn mates_with(&mut self, _move: &Move, depth: usize) -> bool {
+        self.may_mate(_move, depth) && self.with_move(_move, |game| game.mated_within(depth)).unwrap_or(false)
+    }
+
+    /// The moves that force checkmate in at most `depth` moves.
+    fn mating_moves_within(&mut self, depth: usize) -> Vec<Move> {
+        let mut moves = MoveList::new();
+        self.generate_moves(&mut moves);
+        moves.as_slice().iter().copied().filter(|_move| self.mates_with(_move, depth)).collect()
+    }
+
+    /// Tells if the side to move, whose opponent just moved, is mated now
+    /// or can not avoid mate in `depth` more moves of the opponent.
+    fn mated_within(&mut self, depth: usize) -> bool {
+        if depth == 1 && self.is_standard() && !self.is_check() {
+            return false;
+        }
+        let mut replies = MoveList::new();
+        self.generate_moves(&mut replies);
+        if self.is_mated(&replies) {
+            return true;
+        }
+        if depth == 1 || replies.is_empty() || self.outcome.is_some() {
+            return false;
+        }
+        replies.as_slice().iter().all(|reply| {
+            self.with_move(reply, |game| game.outcome.is_none() && game.can_mate(depth - 1)).unwrap_or(true)
+        })
+    }
+
+    /// The line of the mate `_move` gives in `depth` moves, which must be
+    /// the fastest it gives, with the longest defences.
+    fn mate_line(&mut self, _move: &Move, depth: usize) -> Option<Vec<Move>> {
+        let mut line = vec![*_move];
+        let rest = self.with_move(_move, |game| {
+            let mut replies = MoveList::new();
+            game.generate_moves(&mut replies);
+            if game.is_mated(&replies) {
+                return Some(Vec::new());
+            }
+
+            // the defence that holds out longest
+            let mut longest = None;
+            for reply in &replies {
+                if let Some(Some(distance)) = game.with_move(reply, |game| game.mate_distance(depth - 1)) {
+                    if longest.is_none_or(|(_, longest)| distance >= longest) {
+                        longest = Some((*reply, distance));
+                    }
+                }
+            }
+            let (reply, distance) = longest?;
+            let mut rest = vec![reply];
+            rest.extend(game.with_move(&reply, |game| {
+                let next = *game.mating_moves_within(distance).first()?;
+                game.mate_line(&next, distance)
+            })??);
+            Some(rest)
+        })??;
+        line.extend(rest);
+        Some(line)
+    }
+
+    /// A last move can only mate by giving check. Captures are always tried,
+    /// as in some variants they change more than the board shows. In
+    /// standard chess the check is looked for after playing the move, which
+    /// is cheaper.
+    fn may_mate(&self, _move: &Move, depth: usize) -> bool {
+        depth > 1
+            || self.is_standard()
+            || is_capture(self, _move)
+            || self.variant.king_attacked(self, &self.board_after(_move), opposite(self.turn))
+    }
+
+    /// Tells if every defence, by the side to move, has a single fastest
+    /// mate, and so on to the end. Mate in one may be given any way.
+    fn unique_after(&mut self, depth: usize) -> bool {
+        let mut replies = MoveList::new();
+        self.generate_moves(&mut replies);
+        if self.is_mated(&replies) {
+            return true;
+        }
+        replies.as_slice().iter().all(|reply| {
+            self.with_move(reply, |game| {
+                let distance = match game.mate_distance(depth) {
+                    Some(distance) => distance,
+                    None => return false,
+                };
+                if distance == 1 {
+                    return true;
+                }
+                match game.mating_moves_within(distance).as_slice() {
+                    [_move] => game.with_move(_move, |game| game.unique_after(distance - 1)).unwrap_or(false),
+                    _ => false,
+                }
+            })
+            .unwrap_or(true)
+        })
+    }
+
+    /// Tells if the side to move, with `moves` to choose from, has been
+    /// checkmated.
+    fn is_mated(&self, moves: &MoveList) -> bool {
+        if self.is_standard() {
+            moves.is_empty() && self.is_check()
+        } else {
+            self.is_checkmate()
+        }
+    }
+
+    /// Standard chess and Chess960, where the search can use
+    /// `make_move_unchecked`.
+    fn is_standard(&self) -> bool {
+        self.variant.name() == "Standard"
+    }
+
+    /// Plays `_move`, runs `f` on the new position and takes the move back.
+    /// None if the move is not allowed.
+    fn with_move<T>(&mut self, _move: &Move, f: impl FnOnce(&mut Game) -> T) -> Option<T> {
+        let played = if self.is_standard() {
+            Played::Unmake(self.make_move_unchecked(_move))
+        } else {
+            let saved = self.clone();
+            if !self.replay(_move) {
+                *self = saved;
+                return None;
+            }
+            Played::Restore(Box::new(saved))
+        };
+        let result = f(self);
+        match played {
+            Played::Unmake(undo) => self.unmake_move(_move, undo),
+            Played::Restore(saved) => *self = *saved,
+        }
+        Some(result)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::{Game, Move};
+
+    fn uci(moves: &[&str]) -> Vec<Move> {
+        moves.iter().map(|_move| Move::from_uci(_move).unwrap()).collect()
+    }
+
+    #[test]
+    fn find_mate() {
+        let game = Game::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        assert_eq!(game.find_mate(3), Some(uci(&["a1a8"])));
+
+        // the rooks walk up the board
+        let game = Game::from_fen("7k/8/8/8/8/8/R7/1R4K1 w - - 0 1").unwrap();
+        assert_eq!(game.find_mate(1), None);
+        let line = game.find_mate(2).unwrap();
+        assert_eq!(line.len(), 3);
+        let mut played = game.clone();
+        for _move in &line {
+            assert!(played.replay(_move));
+        }
+        assert!(played.is_checkmate());
+
+        assert_eq!(Game::new().find_mate(2), None);
+    }
+
+    #[test]
+    fn unique_mate() {
+        let game = Game::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        assert!(game.has_unique_mate(1));
+        let game = Game::from_fen("6k1/5ppp/8/8/8/8/8/R3R1K1 w - - 0 1").unwrap();
+        assert_eq!(game.mating_moves(1), uci(&["a1a8", "e1e8"]));
+        assert!(!game.has_unique_mate(1));
+        // Morphy's problem, 1. Ra6 bxa6 2. b7#
+        let game = Game::from_fen("kbK5/pp6/1P6/8/8/8/8/R7 w - - 0 1").unwrap();
+        assert_eq!(game.find_mate(2), Some(uci(&["a1a6", "b7a6", "b6b7"])));
+        assert!(game.has_unique_mate(2));
+        let game = Game::from_fen("7k/8/8/8/8/8/R7/1R4K1 w - - 0 1").unwrap();
+        assert!(game.mating_moves(2).len() > 1);
+        assert!(!game.has_unique_mate(2));
+        // only the promotion to a knight mates
+        let game = Game::from_fen("6br/5P1k/6pp/8/8/2B5/8/K7 w - - 0 1").unwrap();
+        assert_eq!(game.mating_moves(1), uci(&["f7f8n"]));
+        assert!(game.has_unique_mate(1));
+    }
+}
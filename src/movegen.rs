@@ -0,0 +1,474 @@
+//! Move generation for search, which fills a list on the stack instead of
+//! allocating.
+//!
+//! ```
+//! use erikrub_chess_lib::movegen::MoveList;
+//! use erikrub_chess_lib::Game;
+//!
+//! let game = Game::new();
+//! let mut moves = MoveList::new();
+//! game.generate_moves(&mut moves);
+//! assert_eq!(moves.len(), 20);
+//!
+//! moves.clear();
+//! game.generate_captures(&mut moves);
+//! assert!(moves.is_empty());
+//! ```
+
+use std::fmt;
+use std::ops::Deref;
+
+use crate::fen::opposite;
+use crate::variant::is_capture;
+use crate::{Game, Move, Piece, PieceColor, PieceType};
+
+/// The most moves a list can hold. Standard chess never has more than 218
+/// legal moves, but with drops and promoted queens a Crazyhouse position can
+/// have close to 500, so the list has room for twice that.
+pub const MAX_MOVES: usize = 1024;
+
+/// Moves in an array of fixed size, so that filling it does not allocate.
+#[derive(Clone)]
+pub struct MoveList {
+    moves: [Move; MAX_MOVES],
+    len: usize,
+}
+
+impl MoveList {
+    pub fn new() -> MoveList {
+        MoveList {
+            moves: [Move::new(0, 0); MAX_MOVES],
+            len: 0,
+        }
+    }
+
+    /// Adds a move at the end. Panics if the list already has `MAX_MOVES`.
+    pub fn push(&mut self, _move: Move) {
+        self.moves[self.len] = _move;
+        self.len += 1;
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    pub fn as_slice(&self) -> &[Move] {
+        &self.moves[..self.len]
+    }
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        MoveList::new()
+    }
+}
+
+impl Deref for MoveList {
+    type Target = [Move];
+
+    fn deref(&self) -> &[Move] {
+        self.as_slice()
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a Move;
+    type IntoIter = std::slice::Iter<'a, Move>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.as_slice().iter()
+    }
+}
+
+impl fmt::Debug for MoveList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Kind {
+    All,
+    Captures,
+    Quiets,
+    Evasions,
+}
+
+const KNIGHT_JUMPS: [(i16, i16); 8] = [(-2, -1), (-2, 1), (-1, 2), (1, 2), (2, 1), (2, -1), (1, -2), (-1, -2)];
+
+impl Game {
+    /// Adds the legal moves of the side to move to `moves`, with a move for
+    /// each piece a pawn can promote to. Unlike `get_legal_moves` nothing is
+    /// allocated in standard chess and Chess960. Other variants get their
+    /// moves from `get_legal_moves`.
+    pub fn generate_moves(&self, moves: &mut MoveList) {
+        self.generate(moves, Kind::All);
+    }
+
+    /// Adds the legal moves that take a piece, en passant included.
+    pub fn generate_captures(&self, moves: &mut MoveList) {
+        self.generate(moves, Kind::Captures);
+    }
+
+    /// Adds the legal moves that do not take a piece, castling and
+    /// promotions without a capture included.
+    pub fn generate_quiets(&self, moves: &mut MoveList) {
+        self.generate(moves, Kind::Quiets);
+    }
+
+    /// Adds the legal moves out of check. Adds nothing when the side to
+    /// move is not in check.
+    pub fn generate_evasions(&self, moves: &mut MoveList) {
+        self.generate(moves, Kind::Evasions);
+    }
+
+    fn generate(&self, moves: &mut MoveList, kind: Kind) {
+        if self.promotion_pos.is_some() {
+            return;
+        }
+        if self.variant.name() != "Standard" {
+            self.generate_from_legal_moves(moves, kind);
+            return;
+        }
+
+        let mut generator = Generator::new(self, moves, kind);
+        if kind == Kind::Evasions && generator.checkers == 0 {
+            return;
+        }
+        for pos in 0..64 {
+            let (piecetype, hasmoved) = match &self.gameboard[pos] {
+                Some(piece) if piece.piececolor == self.turn => (piece.piecetype, piece.hasmoved),
+                _ => continue,
+            };
+            match piecetype {
+                PieceType::King => {
+                    generator.slide(pos, &[0, 1, 2, 3, 4, 5, 6, 7], 1);
+                    generator.castle(pos);
+                }
+                PieceType::Queen => generator.slide(pos, &[0, 1, 2, 3, 4, 5, 6, 7], 7),
+                PieceType::Rook => generator.slide(pos, &[0, 2, 4, 6], 7),
+                PieceType::Bishop => generator.slide(pos, &[1, 3, 5, 7], 7),
+                PieceType::Knight => generator.jump(pos),
+                PieceType::Pawn => generator.pawn(pos, hasmoved),
+            }
+        }
+    }
+
+    /// The moves of a variant, which may have rules this module does not
+    /// know, taken from `get_legal_moves`.
+    fn generate_from_legal_moves(&self, moves: &mut MoveList, kind: Kind) {
+        if kind == Kind::Evasions && !self.is_check() {
+            return;
+        }
+        for _move in self.moves_with_promotions() {
+            let capture = is_capture(self, &_move);
+            let wanted = match kind {
+                Kind::Captures => capture,
+                Kind::Quiets => !capture,
+                Kind::All | Kind::Evasions => true,
+            };
+            if wanted {
+                moves.push(_move);
+            }
+        }
+    }
+}
+
+/// Tries the moves of the pieces on a copy of the board kept on the stack.
+struct Generator<'a> {
+    game: &'a Game,
+    moves: &'a mut MoveList,
+    kind: Kind,
+    board: [Option<Piece>; 64],
+    king: Option<usize>,
+    checkers: usize,
+    checker: usize,
+}
+
+impl<'a> Generator<'a> {
+    fn new(game: &'a Game, moves: &'a mut MoveList, kind: Kind) -> Generator<'a> {
+        let king = (0..64).find(|pos| {
+            matches!(&game.gameboard[*pos], Some(piece) if piece.piecetype == PieceType::King && piece.piececolor == game.turn)
+        });
+        let (mut checkers, mut checker) = (0, 0);
+        if let Some(king) = king {
+            game.visit_attackers(&game.gameboard, king, opposite(game.turn), |pos| {
+                checkers += 1;
+                checker = pos;
+                checkers < 2
+            });
+        }
+        Generator {
+            game,
+            moves,
+            kind,
+            board: std::array::from_fn(|pos| game.gameboard[pos].clone()),
+            king,
+            checkers,
+            checker,
+        }
+    }
+
+    /// Moves along `directions`, at most `range` squares, until a piece is
+    /// in the way.
+    fn slide(&mut self, pos: usize, directions: &[usize], range: i16) {
+        let distances = self.game.distances.get(&pos).unwrap();
+        for direction in directions {
+            let mut newpos = pos;
+            for _ in 0..distances[*direction].min(range) {
+                newpos = (newpos as i16 + self.game.movements[*direction]) as usize;
+                match &self.game.gameboard[newpos] {
+                    Some(piece) => {
+                        if piece.piececolor != self.game.turn {
+                            self.try_move(pos, newpos);
+                        }
+                        break;
+                    }
+                    None => self.try_move(pos, newpos),
+                }
+            }
+        }
+    }
+
+    fn jump(&mut self, pos: usize) {
+        let (row, col) = ((pos / 8) as i16, (pos % 8) as i16);
+        for (up, right) in KNIGHT_JUMPS {
+            let (r, c) = (row + up, col + right);
+            if !(0..8).contains(&r) || !(0..8).contains(&c) {
+                continue;
+            }
+            let newpos = (r * 8 + c) as usize;
+            match &self.game.gameboard[newpos] {
+                Some(piece) if piece.piececolor == self.game.turn => (),
+                _ => self.try_move(pos, newpos),
+            }
+        }
+    }
+
+    fn pawn(&mut self, pos: usize, hasmoved: bool) {
+        let (forward, captures) = match self.game.turn {
+            PieceColor::White => (0, [7, 1]),
+            PieceColor::Black => (4, [5, 3]),
+        };
+        let distances = self.game.distances.get(&pos).unwrap();
+        if distances[forward] == 0 {
+            return;
+        }
+        let step = self.game.movements[forward];
+        let newpos = (pos as i16 + step) as usize;
+        if self.game.gameboard[newpos].is_none() {
+            self.try_move(pos, newpos);
+            let double = (newpos as i16 + step) as usize;
+            if !hasmoved && distances[forward] >= 2 && self.game.gameboard[double].is_none() {
+                self.try_move(pos, double);
+            }
+        }
+        for direction in captures {
+            if distances[direction] == 0 {
+                continue;
+            }
+            let newpos = (pos as i16 + self.game.movements[direction]) as usize;
+            let takes = match &self.game.gameboard[newpos] {
+                Some(piece) => piece.piececolor != self.game.turn,
+                None => self.game.en_passant_capture(&Move::new(pos, newpos)).is_some(),
+            };
+            if takes {
+                self.try_move(pos, newpos);
+            }
+        }
+    }
+
+    /// Castling moves, which never take a piece and can not get out of check.
+    fn castle(&mut self, kingpos: usize) {
+        if self.kind != Kind::All && self.kind != Kind::Quiets {
+            return;
+        }
+        let game = self.game;
+        for rook in game.castling.iter() {
+            if *rook / 8 != kingpos / 8 {
+                continue;
+            }
+            let newpos = if game.chess960 {
+                *rook
+            } else if *rook > kingpos {
+                kingpos + 2
+            } else {
+                kingpos - 2
+            };
+            let castling = match game.castling_move(kingpos, newpos) {
+                Some(castling) => castling,
+                None => continue,
+            };
+            if !game.castling_allowed(&castling, kingpos, game.turn) {
+                continue;
+            }
+
+            let king = self.board[kingpos].take();
+            let rook = self.board[castling.rook].take();
+            self.board[castling.king_to] = king;
+            self.board[castling.rook_to] = rook;
+            let attacked = game.square_attacked(&self.board, castling.king_to, opposite(game.turn));
+            let king = self.board[castling.king_to].take();
+            let rook = self.board[castling.rook_to].take();
+            self.board[kingpos] = king;
+            self.board[castling.rook] = rook;
+            if !attacked {
+                self.moves.push(Move::new(kingpos, newpos));
+            }
+        }
+    }
+
+    /// Adds the move, or a move for each promotion piece, if it is of the
+    /// kind asked for and does not leave the king attacked.
+    fn try_move(&mut self, from: usize, to: usize) {
+        let game = self.game;
+        let _move = Move::new(from, to);
+        let en_passant = game.en_passant_capture(&_move);
+        let capture = game.gameboard[to].is_some() || en_passant.is_some();
+        let wanted = match self.kind {
+            Kind::All => true,
+            Kind::Captures => capture,
+            Kind::Quiets => !capture,
+            // In double check only the king can move, otherwise a piece must
+            // take the checker or step in between.
+            Kind::Evasions => {
+                Some(from) == self.king
+                    || (self.checkers == 1
+                        && (to == self.checker || en_passant == Some(self.checker) || self.blocks(to)))
+            }
+        };
+        if !wanted {
+            return;
+        }
+
+        let taken = en_passant.map(|pos| self.board[pos].take());
+        let piece = self.board[from].take();
+        let target = std::mem::replace(&mut self.board[to], piece);
+        let king = if Some(from) == self.king { Some(to) } else { self.king };
+        let attacked = king.is_some_and(|king| game.square_attacked(&self.board, king, opposite(game.turn)));
+        self.board[from] = std::mem::replace(&mut self.board[to], target);
+        if let (Some(pos), Some(taken)) = (en_passant, taken) {
+            self.board[pos] = taken;
+        }
+        if attacked {
+            return;
+        }
+
+        let pawn = matches!(&game.gameboard[from], Some(piece) if piece.piecetype == PieceType::Pawn);
+        if pawn && (to / 8 == 0 || to / 8 == 7) {
+            for piecetype in game.variant.promotion_pieces() {
+                self.moves.push(Move {
+                    promotion: Some(*piecetype),
+                    .._move
+                });
+            }
+        } else {
+            self.moves.push(_move);
+        }
+    }
+
+    /// Tells if `pos` is between the king and a sliding piece that checks it.
+    fn blocks(&self, pos: usize) -> bool {
+        let king = match self.king {
+            Some(king) => king,
+            None => return false,
+        };
+        let (row, col) = ((king / 8) as i16, (king % 8) as i16);
+        let (checker_row, checker_col) = ((self.checker / 8) as i16, (self.checker % 8) as i16);
+        let (up, right) = (checker_row - row, checker_col - col);
+        if up != 0 && right != 0 && up.abs() != right.abs() {
+            return false;
+        }
+        let steps = up.abs().max(right.abs());
+        (1..steps).any(|step| {
+            let square = (row + up / steps * step) * 8 + col + right / steps * step;
+            square as usize == pos
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MoveList;
+    use crate::variant::{Atomic, Crazyhouse};
+    use crate::{Game, Move};
+
+    fn sorted(moves: &[Move]) -> Vec<String> {
+        let mut moves: Vec<String> = moves.iter().map(|_move| _move.to_string()).collect();
+        moves.sort();
+        moves
+    }
+
+    const POSITIONS: [&str; 6] = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        "8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1",
+    ];
+
+    #[test]
+    fn same_as_legal_moves() {
+        for fen in POSITIONS {
+            let game = Game::from_fen(fen).unwrap();
+            let mut moves = MoveList::new();
+            game.generate_moves(&mut moves);
+            assert_eq!(sorted(&moves), sorted(&game.moves_with_promotions()), "{}", fen);
+
+            // captures and quiet moves together are all moves
+            let mut split = MoveList::new();
+            game.generate_captures(&mut split);
+            assert!(split.iter().all(|_move| crate::variant::is_capture(&game, _move)));
+            game.generate_quiets(&mut split);
+            assert_eq!(sorted(&split), sorted(&moves), "{}", fen);
+        }
+        let game = Game::from_fen_chess960("1r2k1r1/8/8/8/8/8/8/1R2K1R1 w GBgb - 0 1").unwrap();
+        let mut moves = MoveList::new();
+        game.generate_moves(&mut moves);
+        assert_eq!(sorted(&moves), sorted(&game.moves_with_promotions()));
+    }
+
+    #[test]
+    fn evasions() {
+        let game = Game::new();
+        let mut moves = MoveList::new();
+        game.generate_evasions(&mut moves);
+        assert!(moves.is_empty());
+
+        // a single check can be blocked, a double check only escaped by the king
+        for fen in ["4k3/8/8/8/1b6/R7/8/4K1N1 w - - 0 1", "4k3/8/8/8/1b6/8/8/r3K3 w - - 0 1", "3rk3/8/8/8/1b6/8/8/4K3 w - - 0 1"] {
+            let game = Game::from_fen(fen).unwrap();
+            let mut moves = MoveList::new();
+            game.generate_evasions(&mut moves);
+            assert_eq!(sorted(&moves), sorted(&game.moves_with_promotions()), "{}", fen);
+        }
+        // taking the checking pawn en passant
+        let game = Game::from_fen("8/8/8/4k3/3Pp3/8/8/4K3 b - d3 0 1").unwrap();
+        let mut moves = MoveList::new();
+        game.generate_evasions(&mut moves);
+        assert!(moves.contains(&Move::from_uci("e4d3").unwrap()));
+        assert_eq!(sorted(&moves), sorted(&game.moves_with_promotions()));
+    }
+
+    #[test]
+    fn variants_and_promotions() {
+        let game = Game::from_fen("8/1P6/8/8/8/8/8/k1K5 w - - 0 1").unwrap();
+        let mut moves = MoveList::new();
+        game.generate_quiets(&mut moves);
+        let promotions = moves.iter().filter(|_move| _move.promotion.is_some()).count();
+        assert_eq!(promotions, 4);
+
+        let game = Game::from_fen_variant("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2", Atomic).unwrap();
+        let mut moves = MoveList::new();
+        game.generate_captures(&mut moves);
+        assert_eq!(sorted(&moves), vec!["e4d5".to_string()]);
+
+        // drops give far more moves than standard chess ever has
+        let game = Game::from_fen_variant("4k3/8/8/8/8/8/8/4K3[QRBNPqrbnp] w - - 0 1", Crazyhouse).unwrap();
+        let mut moves = MoveList::new();
+        game.generate_moves(&mut moves);
+        assert_eq!(moves.len(), 301);
+        assert_eq!(game.find_mate(1), None);
+    }
+}
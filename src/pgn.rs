@@ -0,0 +1,439 @@
+use std::sync::Arc;
+
+use crate::variant::{Standard, Variant};
+use crate::{square_index, square_name, Game, GameState, Move, PieceColor, PieceType};
+
+impl Game {
+    /// Standard algebraic notation for a move of the side to move, e.g.
+    /// "Nf3", "exd5", "Rad1", "e8=Q+" or the drop "N@f3". None if the move is
+    /// not allowed.
+    pub fn move_to_san(&self, _move: &Move) -> Option<String> {
+        let legal = self.get_legal_moves();
+        if !legal.iter().any(|m| m.from == _move.from && m.to == _move.to && m.drop == _move.drop) {
+            return None;
+        }
+        let mut san = String::new();
+        if let Some(piecetype) = _move.drop {
+            san.push(piece_letter(piecetype));
+            san.push('@');
+            san.push_str(&square_name(_move.to));
+            return Some(self.check_suffix(_move, san));
+        }
+        let piece = self.gameboard[_move.from].as_ref()?;
+        let is_capture = self.gameboard[_move.to].is_some() || self.en_passant_capture(_move).is_some();
+
+        if let Some(castling) = self.castling_move(_move.from, _move.to) {
+            if castling.kingside {
+                san.push_str("O-O");
+            } else {
+                san.push_str("O-O-O");
+            }
+        } else if piece.piecetype == PieceType::Pawn {
+            if is_capture {
+                san.push(square_name(_move.from).as_bytes()[0] as char);
+            }
+        } else {
+            san.push(piece_letter(piece.piecetype));
+
+            let others: Vec<usize> = legal
+                .iter()
+                .filter(|m| m.to == _move.to && m.from != _move.from)
+                .filter(|m| match &self.gameboard[m.from] {
+                    Some(other) => other.piecetype == piece.piecetype,
+                    None => false,
+                })
+                .map(|m| m.from)
+                .collect();
+            if !others.is_empty() {
+                let from = square_name(_move.from);
+                if others.iter().all(|pos| pos % 8 != _move.from % 8) {
+                    san.push_str(&from[0..1]);
+                } else if others.iter().all(|pos| pos / 8 != _move.from / 8) {
+                    san.push_str(&from[1..2]);
+                } else {
+                    san.push_str(&from);
+                }
+            }
+        }
+        if san.starts_with('O') {
+            // castling, nothing more to add
+        } else {
+            if is_capture {
+                san.push('x');
+            }
+            san.push_str(&square_name(_move.to));
+            if let Some(promotion) = _move.promotion {
+                san.push('=');
+                san.push(piece_letter(promotion));
+            }
+        }
+
+        Some(self.check_suffix(_move, san))
+    }
+
+    /// Adds "+" or "#" to `san` if `_move` gives check or mate.
+    fn check_suffix(&self, _move: &Move, mut san: String) -> String {
+        let mut after = self.clone();
+        if after.replay(_move) && after.is_check() {
+            if after.get_legal_moves().is_empty() {
+                san.push('#');
+            } else {
+                san.push('+');
+            }
+        }
+        san
+    }
+
+    /// Finds the move meant by a move in standard algebraic notation, e.g.
+    /// "Nf3", "exd5", "e8=Q" or "O-O". None if no move, or more than one,
+    /// matches.
+    pub fn parse_san(&self, san: &str) -> Option<Move> {
+        let san = san.trim_end_matches(['+', '#', '!', '?']);
+        if !san.is_ascii() || san.len() < 2 {
+            return None;
+        }
+
+        if let Some((piece, square)) = san.split_once('@') {
+            let piecetype = match piece {
+                "" | "P" => PieceType::Pawn,
+                _ => letter_piece(piece)?,
+            };
+            let _move = Move::new_drop(piecetype, square_index(square)?);
+            return self.get_legal_moves().into_iter().find(|m| *m == _move);
+        }
+
+        let kingside = match san {
+            "O-O" | "0-0" => Some(true),
+            "O-O-O" | "0-0-0" => Some(false),
+            _ => None,
+        };
+        if let Some(kingside) = kingside {
+            return self.get_legal_moves().into_iter().find(|_move| {
+                match self.castling_move(_move.from, _move.to) {
+                    Some(castling) => castling.kingside == kingside,
+                    None => false,
+                }
+            });
+        }
+
+        let (body, promotion) = match san.split_once('=') {
+            Some((body, piece)) => (body, Some(letter_piece(piece)?)),
+            None => match san.as_bytes()[san.len() - 1] {
+                b'Q' | b'R' | b'B' | b'N' => (&san[..san.len() - 1], Some(letter_piece(&san[san.len() - 1..])?)),
+                _ => (san, None),
+            },
+        };
+        let allowed = self.variant.promotion_pieces();
+        if promotion.is_some_and(|piece| !allowed.contains(&piece)) || body.len() < 2 {
+            return None;
+        }
+
+        let (piecetype, body) = match letter_piece(&body[0..1]) {
+            Some(piecetype) => (piecetype, &body[1..]),
+            None => (PieceType::Pawn, body),
+        };
+        if body.len() < 2 {
+            return None;
+        }
+        let to = square_index(&body[body.len() - 2..])?;
+        let hint: Vec<u8> = body[..body.len() - 2].bytes().filter(|c| *c != b'x').collect();
+
+        let mut found: Option<Move> = None;
+        for _move in self.get_legal_moves() {
+            if _move.to != to {
+                continue;
+            }
+            match &self.gameboard[_move.from] {
+                Some(piece) if piece.piecetype == piecetype => (),
+                _ => continue,
+            }
+            let from = square_name(_move.from);
+            if !hint.iter().all(|c| from.as_bytes().contains(c)) {
+                continue;
+            }
+            if found.is_some() {
+                return None;
+            }
+            found = Some(Move { promotion, .._move });
+        }
+        found
+    }
+
+    /// Plays a move in standard algebraic notation. Returns the new state, or
+    /// None if the move is not allowed.
+    pub fn make_san_move(&mut self, san: &str) -> Option<GameState> {
+        let _move = self.parse_san(san)?;
+        if self.replay(&_move) {
+            Some(self.state)
+        } else {
+            None
+        }
+    }
+
+    /// The game in Portable Game Notation. Unknown tags are written as "?".
+    pub fn to_pgn(&self) -> String {
+        let result = match self.outcome {
+            Some(outcome) => outcome.result(),
+            None => "*",
+        };
+        let mut pgn = self.pgn_header(&[], result);
+
+        let mut game = self.start_position();
+        let mut tokens: Vec<String> = Vec::new();
+        for (i, _move) in self.history.iter().enumerate() {
+            if game.turn == PieceColor::White {
+                tokens.push(format!("{}.", game.fullmove_number));
+            } else if i == 0 {
+                tokens.push(format!("{}...", game.fullmove_number));
+            }
+            tokens.push(game.move_to_san(_move).unwrap());
+            game.replay(_move);
+        }
+        tokens.push(result.to_string());
+        pgn.push_str(&wrap_movetext(&tokens));
+        pgn
+    }
+
+    /// The tag pairs of a game starting from this game's start position and
+    /// an empty line. `tags` give values to the Seven Tag Roster, other tags
+    /// are written after it.
+    pub(crate) fn pgn_header(&self, tags: &[(String, String)], result: &str) -> String {
+        let mut pgn = String::new();
+        let value = |tag: &str, unknown: &'static str| {
+            tags.iter().find(|(name, _)| name == tag).map_or(unknown.to_string(), |(_, value)| value.clone())
+        };
+        for (tag, unknown) in [
+            ("Event", "?"),
+            ("Site", "?"),
+            ("Date", "????.??.??"),
+            ("Round", "?"),
+            ("White", "?"),
+            ("Black", "?"),
+        ] {
+            pgn.push_str(&format!("[{} \"{}\"]\n", tag, value(tag, unknown)));
+        }
+        pgn.push_str(&format!("[Result \"{}\"]\n", result));
+        for (name, value) in tags {
+            let derived = ["Event", "Site", "Date", "Round", "White", "Black", "Result", "Variant", "SetUp", "FEN"];
+            if !derived.contains(&name.as_str()) {
+                pgn.push_str(&format!("[{} \"{}\"]\n", name, value));
+            }
+        }
+        if self.chess960 {
+            pgn.push_str("[Variant \"Chess960\"]\n");
+        } else if self.variant.name() != "Standard" {
+            pgn.push_str(&format!("[Variant \"{}\"]\n", self.variant.name()));
+        }
+        if self.chess960 || self.start_fen != self.variant.start_fen() {
+            pgn.push_str("[SetUp \"1\"]\n");
+            pgn.push_str(&format!("[FEN \"{}\"]\n", self.start_fen));
+        }
+        pgn.push('\n');
+        pgn
+    }
+
+    /// Loads the first game of a PGN text. Comments, variations and NAGs
+    /// are skipped. None if a move is not allowed or the FEN tag is not valid.
+    /// A "Variant" tag selects Chess960 or a variant built into the crate.
+    pub fn from_pgn(pgn: &str) -> Option<Game> {
+        let (mut game, _, movetext) = read_pgn(pgn)?;
+        for token in movetext_tokens(&movetext) {
+            game.make_san_move(&token)?;
+        }
+        Some(game)
+    }
+}
+
+/// PGN tags by name, in the order they were read.
+pub(crate) type Tags = Vec<(String, String)>;
+
+/// The start position, the tags and the movetext of the first game of a PGN
+/// text. None if the FEN or Variant tag is not valid.
+pub(crate) fn read_pgn(pgn: &str) -> Option<(Game, Tags, String)> {
+    let mut tags: Tags = Vec::new();
+    let mut fen: Option<String> = None;
+    let mut chess960 = false;
+    let mut variant: Arc<dyn Variant> = Arc::new(Standard);
+    let mut movetext = String::new();
+    for line in pgn.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            if !movetext.trim().is_empty() {
+                break;
+            }
+            match parse_tag(line) {
+                Some(("FEN", value)) => fen = Some(value.to_string()),
+                Some(("Variant", value)) => {
+                    let lowercase = value.to_lowercase();
+                    chess960 = lowercase.contains("960") || lowercase.contains("fischerandom");
+                    if !chess960 {
+                        variant = crate::variant::from_name(value)?.into();
+                    }
+                }
+                Some((name, value)) => tags.push((name.to_string(), value.to_string())),
+                None => (),
+            }
+        } else if !line.starts_with('%') {
+            movetext.push_str(line);
+            movetext.push('\n');
+        }
+    }
+
+    let fen = fen.unwrap_or(variant.start_fen().to_string());
+    Some((Game::load_fen(&fen, chess960, variant)?, tags, movetext))
+}
+
+/// Joins movetext tokens with spaces into lines shorter than 80 characters.
+pub(crate) fn wrap_movetext(tokens: &[String]) -> String {
+    let mut movetext = String::new();
+    let mut line_length = 0;
+    for token in tokens {
+        if line_length > 0 && line_length + token.len() >= 80 {
+            movetext.push('\n');
+            line_length = 0;
+        } else if line_length > 0 {
+            movetext.push(' ');
+            line_length += 1;
+        }
+        line_length += token.len();
+        movetext.push_str(token);
+    }
+    movetext.push('\n');
+    movetext
+}
+
+/// `[Name "Value"]` -> ("Name", "Value")
+fn parse_tag(line: &str) -> Option<(&str, &str)> {
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?;
+    let (name, value) = inner.split_once(' ')?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+    Some((name, value))
+}
+
+/// Splits PGN movetext into SAN moves, dropping move numbers, results,
+/// comments, variations and NAGs.
+fn movetext_tokens(movetext: &str) -> Vec<String> {
+    let mut cleaned = String::new();
+    let mut depth = 0;
+    let mut chars = movetext.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                }
+                cleaned.push(' ');
+            }
+            ';' => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+                cleaned.push(' ');
+            }
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ if depth > 0 => (),
+            _ => cleaned.push(c),
+        }
+    }
+
+    let mut tokens: Vec<String> = Vec::new();
+    for token in cleaned.split_whitespace() {
+        let token = token.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+        if token.is_empty()
+            || token.starts_with('$')
+            || ["*", "1-0", "0-1", "1/2-1/2"].contains(&token)
+        {
+            continue;
+        }
+        tokens.push(token.to_string());
+    }
+    tokens
+}
+
+fn piece_letter(piecetype: PieceType) -> char {
+    match piecetype {
+        PieceType::King => 'K',
+        PieceType::Queen => 'Q',
+        PieceType::Bishop => 'B',
+        PieceType::Knight => 'N',
+        PieceType::Rook => 'R',
+        PieceType::Pawn => 'P',
+    }
+}
+
+fn letter_piece(letter: &str) -> Option<PieceType> {
+    match letter {
+        "K" => Some(PieceType::King),
+        "Q" => Some(PieceType::Queen),
+        "B" => Some(PieceType::Bishop),
+        "N" => Some(PieceType::Knight),
+        "R" => Some(PieceType::Rook),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Game, GameState, Move, PieceType};
+
+    #[test]
+    fn san_round_trip() {
+        let mut game = Game::new();
+        for san in ["e4", "d5", "exd5", "Nf6", "Nc3", "Nxd5", "Nxd5", "Qxd5"] {
+            let _move = game.parse_san(san).unwrap();
+            assert_eq!(game.move_to_san(&_move).unwrap(), san);
+            game.make_san_move(san).unwrap();
+        }
+        assert_eq!(game.to_fen(), "rnb1kb1r/ppp1pppp/8/3q4/8/8/PPPP1PPP/R1BQKBNR w KQkq - 0 5");
+        assert!(game.make_san_move("Ke3").is_none());
+    }
+
+    #[test]
+    fn disambiguation_and_promotion() {
+        let game = Game::from_fen("4k3/1P6/8/8/8/8/4K3/R6R w - - 0 1").unwrap();
+        assert_eq!(game.move_to_san(&Move::from_uci("a1d1").unwrap()).unwrap(), "Rad1");
+        assert!(game.parse_san("Rd1").is_none());
+        assert_eq!(game.parse_san("Rhd1"), Move::from_uci("h1d1"));
+
+        let promotion = game.parse_san("b8=Q+").unwrap();
+        assert_eq!(promotion.promotion, Some(PieceType::Queen));
+        assert_eq!(game.move_to_san(&promotion).unwrap(), "b8=Q+");
+
+        // too short to have a square after the piece
+        for san in ["Nf", "Qx", "x"] {
+            assert!(game.parse_san(san).is_none(), "{}", san);
+        }
+    }
+
+    #[test]
+    fn pgn_round_trip() {
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 7").unwrap();
+        game.make_san_move("Kd7");
+        game.make_san_move("e4");
+        let pgn = game.to_pgn();
+        assert!(pgn.contains("[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 7\"]"));
+        assert!(pgn.ends_with("\n7... Kd7 8. e4 *\n"));
+
+        let loaded = Game::from_pgn(&pgn).unwrap();
+        assert_eq!(loaded.get_history(), game.get_history());
+
+        let mut game = Game::from_fen("1r4kr/8/8/8/8/8/8/1R4KR w HBhb - 0 1").unwrap();
+        game.make_san_move("O-O").unwrap();
+        assert!(game.parse_san("O-O-O").is_none());
+        game.make_san_move("0-0").unwrap();
+        assert_eq!(game.to_fen(), "1r3rk1/8/8/8/8/8/8/1R3RK1 w - - 2 2");
+        let pgn = game.to_pgn();
+        assert!(pgn.contains("[Variant \"Chess960\"]"));
+        assert!(pgn.ends_with("1. O-O O-O *\n"));
+        assert_eq!(Game::from_pgn(&pgn).unwrap().to_fen(), game.to_fen());
+
+        let loaded = Game::from_pgn("1. e4 {best by test} e5 (1... c5 2. Nf3) 2. Nf3 $1 Nc6 *").unwrap();
+        assert_eq!(loaded.get_history().len(), 4);
+        assert_eq!(loaded.get_game_state(), GameState::InProgress);
+    }
+}
@@ -0,0 +1,209 @@
+//! Tactics puzzles: a position and the moves that solve it.
+//!
+//! ```
+//! use erikrub_chess_lib::puzzle::{Puzzle, PuzzleState};
+//! use erikrub_chess_lib::Move;
+//!
+//! // Morphy's mate in two
+//! let mut puzzle = Puzzle::new("kbK5/pp6/1P6/8/8/8/8/R7 w - - 0 1", &["a1a6", "b7a6", "b6b7"]).unwrap();
+//! assert_eq!(puzzle.hint(), Some("a1".to_string()));
+//! assert_eq!(puzzle.make_move(&Move::from_uci("a1a6").unwrap()), Some(PuzzleState::Playing));
+//! // black's reply has been played
+//! assert_eq!(puzzle.game().get_history().len(), 2);
+//! assert_eq!(puzzle.make_move(&Move::from_uci("b6b7").unwrap()), Some(PuzzleState::Solved));
+//! ```
+
+use crate::{square_name, Game, Move};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PuzzleState {
+    /// Waiting for the solver's next move.
+    Playing,
+    Solved,
+    /// A wrong move was tried. It is not played, and the puzzle is over.
+    Failed,
+}
+
+/// A position and its solution. The solver plays the first move of the
+/// solution and every second move after it, the other moves are played
+/// for the opponent.
+#[derive(Clone)]
+pub struct Puzzle {
+    game: Game,
+    solution: Vec<Move>,
+    played: usize,
+    mate: bool,
+    state: PuzzleState,
+    hints: u32,
+}
+
+impl Puzzle {
+    /// A puzzle from a FEN and the solution in coordinate notation. None if
+    /// the FEN or a move is not valid.
+    pub fn new(fen: &str, solution: &[&str]) -> Option<Puzzle> {
+        let solution: Option<Vec<Move>> = solution.iter().map(|_move| Move::from_uci(_move)).collect();
+        Puzzle::from_game(Game::from_fen(fen)?, solution?)
+    }
+
+    /// A puzzle from the current position of `game`. None if the solution
+    /// is empty or one of its moves is not allowed.
+    pub fn from_game(game: Game, solution: Vec<Move>) -> Option<Puzzle> {
+        if solution.is_empty() {
+            return None;
+        }
+        let mut end = game.clone();
+        for _move in solution.iter() {
+            if !end.moves_with_promotions().contains(_move) {
+                return None;
+            }
+            end.replay(_move);
+        }
+        Some(Puzzle {
+            game,
+            mate: end.is_checkmate(),
+            state: PuzzleState::Playing,
+            solution,
+            played: 0,
+            hints: 0,
+        })
+    }
+
+    /// Tries a move of the solver. A move of the solution is played along
+    /// with the opponent's answer. In a puzzle that ends in mate any move
+    /// that forces mate in the moves left is accepted too: the rest of the
+    /// solution is then replaced by that mate, with the longest defences.
+    /// Any other move fails the puzzle. None if the move is not allowed or
+    /// the puzzle is over.
+    pub fn make_move(&mut self, _move: &Move) -> Option<PuzzleState> {
+        if self.state != PuzzleState::Playing || !self.game.moves_with_promotions().contains(_move) {
+            return None;
+        }
+        if *_move != self.solution[self.played] {
+            // another move that mates in time takes the rest of the solution's place
+            let remaining = (self.solution.len() - self.played).div_ceil(2);
+            match self.mate.then(|| self.game.mate_line_from(_move, remaining)).flatten() {
+                Some(line) => {
+                    self.solution.truncate(self.played);
+                    self.solution.extend(line);
+                }
+                None => {
+                    self.state = PuzzleState::Failed;
+                    return Some(self.state);
+                }
+            }
+        }
+
+        self.game.replay(_move);
+        self.played += 1;
+        if self.game.is_checkmate() || self.played == self.solution.len() {
+            self.state = PuzzleState::Solved;
+            return Some(self.state);
+        }
+        self.game.replay(&self.solution[self.played]);
+        self.played += 1;
+        if self.played == self.solution.len() {
+            self.state = PuzzleState::Solved;
+        }
+        Some(self.state)
+    }
+
+    /// The square of the piece the solver should move next, or None when
+    /// the puzzle is over. Counted in `hints_used`.
+    pub fn hint(&mut self) -> Option<String> {
+        if self.state != PuzzleState::Playing {
+            return None;
+        }
+        self.hints += 1;
+        Some(square_name(self.solution[self.played].from))
+    }
+
+    /// The move the solver should play next, or None when the puzzle is over.
+    pub fn expected_move(&self) -> Option<Move> {
+        match self.state {
+            PuzzleState::Playing => Some(self.solution[self.played]),
+            _ => None,
+        }
+    }
+
+    pub fn state(&self) -> PuzzleState {
+        self.state
+    }
+
+    /// How many times `hint` was asked.
+    pub fn hints_used(&self) -> u32 {
+        self.hints
+    }
+
+    /// Tells if the solution ends in checkmate.
+    pub fn is_mate(&self) -> bool {
+        self.mate
+    }
+
+    /// The game with the moves played so far.
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// The solution, with the mate the solver chose instead if they played
+    /// another one.
+    pub fn solution(&self) -> &[Move] {
+        &self.solution
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Puzzle, PuzzleState};
+    use crate::Move;
+
+    fn uci(_move: &str) -> Move {
+        Move::from_uci(_move).unwrap()
+    }
+
+    #[test]
+    fn wrong_move() {
+        let mut puzzle = Puzzle::new("kbK5/pp6/1P6/8/8/8/8/R7 w - - 0 1", &["a1a6", "b7a6", "b6b7"]).unwrap();
+        assert!(puzzle.is_mate());
+        // not allowed
+        assert_eq!(puzzle.make_move(&uci("a1a8")), None);
+        assert_eq!(puzzle.make_move(&uci("a1a5")), Some(PuzzleState::Failed));
+        assert!(puzzle.game().get_history().is_empty());
+        assert_eq!(puzzle.make_move(&uci("a1a6")), None);
+        assert_eq!(puzzle.hint(), None);
+
+        assert!(Puzzle::new("kbK5/pp6/1P6/8/8/8/8/R7 w - - 0 1", &["a1a6", "a7a6"]).is_none());
+        assert!(Puzzle::new("kbK5/pp6/1P6/8/8/8/8/R7 w - - 0 1", &[]).is_none());
+    }
+
+    #[test]
+    fn other_mates() {
+        // both rooks mate on the back rank
+        let mut puzzle = Puzzle::new("6k1/5ppp/8/8/8/8/8/R3R1K1 w - - 0 1", &["a1a8"]).unwrap();
+        assert_eq!(puzzle.expected_move(), Some(uci("a1a8")));
+        assert_eq!(puzzle.make_move(&uci("e1e8")), Some(PuzzleState::Solved));
+        assert_eq!(puzzle.expected_move(), None);
+
+        // a different mate in two, after which the solution's reply a8a7 is
+        // not allowed
+        let mut puzzle = Puzzle::new("k7/8/2K5/8/8/8/8/7Q w - - 0 1", &["c6c7", "a8a7", "h1a1"]).unwrap();
+        assert_eq!(puzzle.make_move(&uci("h1h7")), Some(PuzzleState::Playing));
+        assert_eq!(puzzle.game().get_history().len(), 2);
+        assert_eq!(puzzle.solution(), &[uci("h1h7"), uci("a8b8"), uci("h7b7")]);
+        assert_eq!(puzzle.expected_move(), Some(uci("h7b7")));
+        assert_eq!(puzzle.make_move(&uci("h7b7")), Some(PuzzleState::Solved));
+        // a move that does not mate in time
+        let mut puzzle = Puzzle::new("k7/8/2K5/8/8/8/8/7Q w - - 0 1", &["c6c7", "a8a7", "h1a1"]).unwrap();
+        assert_eq!(puzzle.make_move(&uci("h1h2")), Some(PuzzleState::Failed));
+
+        // the game is over after the mate
+        assert!(Puzzle::new("6k1/5ppp/8/8/8/8/8/R3R1K1 w - - 0 1", &["a1a8", "g8g7"]).is_none());
+        // a tactic that does not end in mate wants the solution's move
+        let fen = "r3k3/8/8/8/8/8/8/R3K2R w - - 0 1";
+        let mut puzzle = Puzzle::new(fen, &["a1a8", "e8e7", "h1h7"]).unwrap();
+        assert!(!puzzle.is_mate());
+        assert_eq!(puzzle.hint(), Some("a1".to_string()));
+        assert_eq!(puzzle.hints_used(), 1);
+        assert_eq!(puzzle.make_move(&uci("a1a8")), Some(PuzzleState::Playing));
+        assert_eq!(puzzle.make_move(&uci("a8a7")), Some(PuzzleState::Failed));
+    }
+}
@@ -0,0 +1,263 @@
+//! Python bindings, enabled with the `python` feature. Build and install
+//! into the current virtualenv with `maturin develop`, then:
+//!
+//! ```python
+//! import erikrub_chess_lib as chess
+//!
+//! game = chess.Game()
+//! game.push_san("e4")
+//! for move in game.legal_moves():
+//!     print(game.san(move))
+//! ```
+//!
+//! Errors are raised as exceptions: `IllegalMoveError` (a `ValueError`) for
+//! moves that are not allowed, `ValueError` for bad squares, FEN or PGN.
+
+use pyo3::create_exception;
+use pyo3::exceptions::{PyIndexError, PyValueError};
+use pyo3::prelude::*;
+
+use crate::{square_index, square_name, Game, GameState, Move, PieceColor, PieceType};
+
+create_exception!(erikrub_chess_lib, IllegalMoveError, PyValueError);
+
+#[pyclass(name = "Move", module = "erikrub_chess_lib", frozen, eq, from_py_object)]
+#[derive(Clone, PartialEq)]
+pub struct PyMove {
+    inner: Move,
+}
+
+#[pymethods]
+impl PyMove {
+    /// Parses coordinate notation such as "e2e4" or "e7e8q".
+    #[staticmethod]
+    fn from_uci(uci: &str) -> PyResult<PyMove> {
+        match Move::from_uci(uci) {
+            Some(inner) => Ok(PyMove { inner }),
+            None => Err(PyValueError::new_err(format!("invalid move \"{}\"", uci))),
+        }
+    }
+
+    #[getter]
+    fn get_from_square(&self) -> String {
+        square_name(self.inner.from)
+    }
+
+    #[getter]
+    fn get_to_square(&self) -> String {
+        square_name(self.inner.to)
+    }
+
+    #[getter]
+    fn promotion(&self) -> Option<PieceType> {
+        self.inner.promotion
+    }
+
+    #[getter]
+    fn drop(&self) -> Option<PieceType> {
+        self.inner.drop
+    }
+
+    fn uci(&self) -> String {
+        self.inner.to_string()
+    }
+
+    fn __str__(&self) -> String {
+        self.inner.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Move.from_uci(\"{}\")", self.inner)
+    }
+
+    fn __hash__(&self) -> u64 {
+        let piece = |piecetype: Option<PieceType>| match piecetype {
+            Some(piecetype) => piecetype as u64 + 1,
+            None => 0,
+        };
+        (self.inner.from as u64) << 24
+            | (self.inner.to as u64) << 16
+            | piece(self.inner.drop) << 8
+            | piece(self.inner.promotion)
+    }
+}
+
+#[pyclass(module = "erikrub_chess_lib")]
+pub struct MoveIterator {
+    moves: std::vec::IntoIter<Move>,
+}
+
+#[pymethods]
+impl MoveIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(mut slf: PyRefMut<'_, Self>) -> Option<PyMove> {
+        slf.moves.next().map(|inner| PyMove { inner })
+    }
+}
+
+#[pyclass(name = "Game", module = "erikrub_chess_lib")]
+pub struct PyGame {
+    game: Game,
+}
+
+#[pymethods]
+impl PyGame {
+    #[new]
+    fn new() -> PyGame {
+        PyGame { game: Game::new() }
+    }
+
+    #[staticmethod]
+    fn from_fen(fen: &str) -> PyResult<PyGame> {
+        match Game::from_fen(fen) {
+            Some(game) => Ok(PyGame { game }),
+            None => Err(PyValueError::new_err(format!("invalid FEN \"{}\"", fen))),
+        }
+    }
+
+    #[staticmethod]
+    fn from_pgn(pgn: &str) -> PyResult<PyGame> {
+        match Game::from_pgn(pgn) {
+            Some(game) => Ok(PyGame { game }),
+            None => Err(PyValueError::new_err("invalid PGN")),
+        }
+    }
+
+    #[getter]
+    fn fen(&self) -> String {
+        self.game.to_fen()
+    }
+
+    fn pgn(&self) -> String {
+        self.game.to_pgn()
+    }
+
+    #[getter]
+    fn state(&self) -> GameState {
+        self.game.get_game_state()
+    }
+
+    #[getter]
+    fn turn(&self) -> PieceColor {
+        self.game.turn
+    }
+
+    #[getter]
+    fn history(&self) -> Vec<PyMove> {
+        self.game
+            .get_history()
+            .iter()
+            .map(|inner| PyMove { inner: *inner })
+            .collect()
+    }
+
+    /// Iterates over every move of the side to move.
+    fn legal_moves(&self) -> MoveIterator {
+        MoveIterator {
+            moves: self.game.get_legal_moves().into_iter(),
+        }
+    }
+
+    /// Squares the piece on `square` can move to.
+    fn moves_from(&self, square: &str) -> PyResult<Vec<String>> {
+        if square_index(square).is_none() {
+            return Err(PyValueError::new_err(format!("invalid square \"{}\"", square)));
+        }
+        Ok(self.game.get_possible_moves(square).unwrap_or_default())
+    }
+
+    /// Moves a piece and returns the new state. A pawn reaching the last
+    /// rank leaves the game in `GameState.SetPromotion`.
+    fn make_move(&mut self, from_square: &str, to_square: &str) -> PyResult<GameState> {
+        let played = self.game.get_history().len();
+        match self.game.make_move(from_square, to_square) {
+            Some(state) if self.game.get_history().len() > played => Ok(state),
+            Some(_) => Err(IllegalMoveError::new_err(format!(
+                "illegal move {}{}",
+                from_square, to_square
+            ))),
+            None => Err(PyValueError::new_err(format!(
+                "invalid square in {}{}",
+                from_square, to_square
+            ))),
+        }
+    }
+
+    /// Plays a `Move`, including its promotion. The game is left as it was
+    /// if the move is not allowed.
+    fn push(&mut self, _move: PyMove) -> PyResult<GameState> {
+        if self.game.moves_with_promotions().contains(&_move.inner) && self.game.replay(&_move.inner) {
+            Ok(self.game.get_game_state())
+        } else {
+            Err(IllegalMoveError::new_err(format!("illegal move {}", _move.inner)))
+        }
+    }
+
+    fn push_uci(&mut self, uci: &str) -> PyResult<GameState> {
+        self.push(PyMove::from_uci(uci)?)
+    }
+
+    fn push_san(&mut self, san: &str) -> PyResult<GameState> {
+        match self.game.make_san_move(san) {
+            Some(state) => Ok(state),
+            None => Err(IllegalMoveError::new_err(format!("illegal move {}", san))),
+        }
+    }
+
+    fn parse_san(&self, san: &str) -> PyResult<PyMove> {
+        match self.game.parse_san(san) {
+            Some(inner) => Ok(PyMove { inner }),
+            None => Err(IllegalMoveError::new_err(format!("illegal move {}", san))),
+        }
+    }
+
+    fn san(&self, _move: PyMove) -> PyResult<String> {
+        match self.game.move_to_san(&_move.inner) {
+            Some(san) => Ok(san),
+            None => Err(IllegalMoveError::new_err(format!("illegal move {}", _move.inner))),
+        }
+    }
+
+    fn set_promotion(&mut self, piece: PieceType) -> PyResult<GameState> {
+        if self.game.promotion_pos.is_none() {
+            return Err(IllegalMoveError::new_err("no promotion is pending"));
+        }
+        if !self.game.variant().promotion_pieces().contains(&piece) {
+            return Err(PyValueError::new_err(format!("can not promote to {:?}", piece)));
+        }
+        self.game.promote(piece);
+        Ok(self.game.get_game_state())
+    }
+
+    /// Takes back the last move and returns it.
+    fn undo(&mut self) -> PyResult<PyMove> {
+        let last = self.game.get_history().last().copied();
+        match (last, self.game.undo()) {
+            (Some(inner), Some(_)) => Ok(PyMove { inner }),
+            _ => Err(PyIndexError::new_err("no move to undo")),
+        }
+    }
+
+    fn __str__(&self) -> String {
+        format!("{:?}", self.game)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Game.from_fen(\"{}\")", self.game.to_fen())
+    }
+}
+
+#[pymodule]
+fn erikrub_chess_lib(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyGame>()?;
+    m.add_class::<PyMove>()?;
+    m.add_class::<MoveIterator>()?;
+    m.add_class::<GameState>()?;
+    m.add_class::<PieceColor>()?;
+    m.add_class::<PieceType>()?;
+    m.add("IllegalMoveError", m.py().get_type::<IllegalMoveError>())?;
+    Ok(())
+}
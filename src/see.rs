@@ -0,0 +1,112 @@
+use std::cmp;
+
+use crate::fen::opposite;
+use crate::{Game, Move, Piece, PieceColor, PieceType};
+
+impl Game {
+    /// Static exchange evaluation: the material the side to move wins, in
+    /// centipawns, if `_move` is played and both sides then keep taking on
+    /// its square with their least valuable piece for as long as it pays.
+    /// Pieces behind a rook, bishop or queen join in once it has taken.
+    /// Apart from promotions, a move that captures nothing gives 0 or less,
+    /// so SEE also tells if a piece is safe on its new square.
+    pub fn see(&self, _move: &Move) -> i32 {
+        if self.castling_move(_move.from, _move.to).is_some() {
+            return 0;
+        }
+        let mut captured = match &self.gameboard[_move.to] {
+            Some(piece) if _move.drop.is_none() => value(piece.piecetype),
+            _ if self.en_passant_capture(_move).is_some() => value(PieceType::Pawn),
+            _ => 0,
+        };
+        if let Some(promotion) = _move.promotion {
+            captured += value(promotion) - value(PieceType::Pawn);
+        }
+
+        let mut board = self.board_after(_move);
+        let (mut on_square, mut side) = match &board[_move.to] {
+            Some(piece) => (value(piece.piecetype), opposite(piece.piececolor)),
+            None => return 0,
+        };
+        let mut gains = vec![captured];
+        while let Some(pos) = self.least_valuable_attacker(&board, _move.to, side) {
+            gains.push(on_square - gains.last().unwrap());
+            on_square = value(board[pos].as_ref().unwrap().piecetype);
+            board[_move.to] = board[pos].take();
+            side = opposite(side);
+        }
+
+        // each side stops taking when that is better for it
+        while gains.len() > 1 {
+            let last = gains.pop().unwrap();
+            let previous = gains.last_mut().unwrap();
+            *previous = -cmp::max(-*previous, last);
+        }
+        gains[0]
+    }
+
+    /// Tells if `see(_move)` is at least `threshold`.
+    pub fn see_ge(&self, _move: &Move, threshold: i32) -> bool {
+        self.see(_move) >= threshold
+    }
+
+    /// The square of the cheapest piece of `side` that can take on `pos`. A
+    /// king only takes when the square is not defended.
+    fn least_valuable_attacker(&self, board: &[Option<Piece>], pos: usize, side: PieceColor) -> Option<usize> {
+        let attacker = self
+            .attackers(board, pos, side)
+            .into_iter()
+            .min_by_key(|square| value(board[*square].as_ref().unwrap().piecetype))?;
+        if board[attacker].as_ref().unwrap().piecetype == PieceType::King
+            && !self.attackers(board, pos, opposite(side)).is_empty()
+        {
+            return None;
+        }
+        Some(attacker)
+    }
+}
+
+/// Piece values in centipawns.
+fn value(piecetype: PieceType) -> i32 {
+    match piecetype {
+        PieceType::Pawn => 100,
+        PieceType::Knight => 300,
+        PieceType::Bishop => 300,
+        PieceType::Rook => 500,
+        PieceType::Queen => 900,
+        PieceType::King => 10000,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Game, Move};
+
+    fn see(fen: &str, uci: &str) -> i32 {
+        Game::from_fen(fen).unwrap().see(&Move::from_uci(uci).unwrap())
+    }
+
+    #[test]
+    fn exchanges() {
+        // free pawn
+        assert_eq!(see("1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1", "e1e5"), 100);
+        // the knight is lost for a pawn, the queen behind the rook joins in
+        assert_eq!(see("1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1", "d3e5"), -200);
+        // rook behind rook
+        assert_eq!(see("4k3/4r3/8/4p3/8/8/4R3/4R1K1 w - - 0 1", "e2e5"), 100);
+        // a quiet move onto an attacked square
+        assert_eq!(see("4k3/8/3p4/8/4N3/8/8/4K3 w - - 0 1", "e4c5"), -300);
+        assert_eq!(see("4k3/8/3p4/8/4N3/8/8/4K3 w - - 0 1", "e4f6"), 0);
+    }
+
+    #[test]
+    fn see_ge() {
+        let game = Game::from_fen("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1").unwrap();
+        let _move = Move::from_uci("e4d5").unwrap();
+        assert!(game.see_ge(&_move, 100));
+        assert!(!game.see_ge(&_move, 101));
+        // the king can not take back a defended piece
+        let game = Game::from_fen("8/8/8/3pk3/4P3/8/8/3QK3 w - - 0 1").unwrap();
+        assert_eq!(game.see(&_move), 100);
+    }
+}
@@ -0,0 +1,137 @@
+//! Serde support, enabled with the `serde` feature.
+//!
+//! A `Game` is written as the position it started from plus every move made
+//! since, e.g. `{"fen":"rnbqkbnr/... w - - 0 1","moves":["e2e4","e7e5"]}`.
+//! Loading replays the moves, so all derived state (check, pending promotion,
+//! possible moves) comes back exactly as it was. A `Move` is written in
+//! coordinate notation ("e7e8q"). Chess960 games add `"chess960":true` and
+//! other variants their name, e.g. `"variant":"Atomic"`. Only variants built
+//! into the crate can be loaded again.
+
+use serde::de::{self, Deserializer};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+
+use std::sync::Arc;
+
+use crate::variant::{self, Standard, Variant};
+use crate::{Game, Move};
+
+#[derive(Serialize, Deserialize)]
+struct GameRecord {
+    fen: String,
+    moves: Vec<Move>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    chess960: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    variant: Option<String>,
+}
+
+impl Serialize for Game {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        GameRecord {
+            fen: self.start_fen.clone(),
+            moves: self.history.clone(),
+            chess960: self.chess960,
+            variant: match self.variant.name() {
+                "Standard" => None,
+                name => Some(name.to_string()),
+            },
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Game {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Game, D::Error> {
+        let record = GameRecord::deserialize(deserializer)?;
+        let variant: Arc<dyn Variant> = match &record.variant {
+            Some(name) => variant::from_name(name)
+                .ok_or_else(|| de::Error::custom(format!("unknown variant \"{}\"", name)))?
+                .into(),
+            None => Arc::new(Standard),
+        };
+        let mut game = Game::load_fen(&record.fen, record.chess960, variant)
+            .ok_or_else(|| de::Error::custom(format!("invalid FEN \"{}\"", record.fen)))?;
+
+        for _move in record.moves.iter() {
+            if !game.replay(_move) {
+                return Err(de::Error::custom(format!("illegal move {}", _move)));
+            }
+        }
+        Ok(game)
+    }
+}
+
+impl Serialize for Move {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Move {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Move, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Move::from_uci(&s).ok_or_else(|| de::Error::custom(format!("invalid move \"{}\"", s)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Game, GameState, Piece, PieceColor, PieceType};
+
+    #[test]
+    fn game_round_trip() {
+        let mut game = Game::new();
+        game.make_move("e2", "e4");
+        game.make_move("d7", "d5");
+        game.make_move("e4", "d5");
+
+        let json = serde_json::to_string(&game).unwrap();
+        assert_eq!(
+            json,
+            "{\"fen\":\"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1\",\"moves\":[\"e2e4\",\"d7d5\",\"e4d5\"]}"
+        );
+        let loaded: Game = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.to_fen(), game.to_fen());
+        assert_eq!(loaded.get_history(), game.get_history());
+        assert_eq!(loaded.get_possible_moves("d8"), game.get_possible_moves("d8"));
+
+        let mut game = Game::new_chess960(518).unwrap();
+        game.make_move("g1", "f3");
+        let json = serde_json::to_string(&game).unwrap();
+        assert!(json.ends_with("\"chess960\":true}"));
+        let loaded: Game = serde_json::from_str(&json).unwrap();
+        assert!(loaded.is_chess960());
+    }
+
+    #[test]
+    fn pending_promotion_round_trip() {
+        let mut game = Game::from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        game.make_move("b7", "b8");
+        assert_eq!(game.get_game_state(), GameState::SetPromotion);
+
+        let loaded: Game = serde_json::from_str(&serde_json::to_string(&game).unwrap()).unwrap();
+        assert_eq!(loaded.get_game_state(), GameState::SetPromotion);
+        assert_eq!(loaded.promotion_pos, Some(1));
+
+        game.set_promotion("q");
+        let loaded: Game = serde_json::from_str(&serde_json::to_string(&game).unwrap()).unwrap();
+        assert_eq!(loaded.to_fen(), "1Q2k3/8/8/8/8/8/8/4K3 b - - 0 1");
+        assert_eq!(loaded.get_history(), game.get_history());
+    }
+
+    #[test]
+    fn illegal_moves_are_rejected() {
+        let json = "{\"fen\":\"rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1\",\"moves\":[\"e2e5\"]}";
+        assert!(serde_json::from_str::<Game>(json).is_err());
+    }
+
+    #[test]
+    fn piece_round_trip() {
+        let piece = Piece::new(PieceType::Knight, PieceColor::Black);
+        let loaded: Piece = serde_json::from_str(&serde_json::to_string(&piece).unwrap()).unwrap();
+        assert_eq!(loaded.get_piecetype(), PieceType::Knight);
+        assert_eq!(loaded.get_piececolor(), PieceColor::Black);
+    }
+}
@@ -0,0 +1,513 @@
+//! Setting up a position piece by piece, e.g. in a puzzle editor.
+//!
+//! ```
+//! use erikrub_chess_lib::setup::BoardBuilder;
+//! use erikrub_chess_lib::{GameState, Piece, PieceColor, PieceType};
+//!
+//! let mut builder = BoardBuilder::new();
+//! builder
+//!     .put("g8", Piece::new(PieceType::King, PieceColor::Black))
+//!     .put("g1", Piece::new(PieceType::King, PieceColor::White))
+//!     .put("a1", Piece::new(PieceType::Rook, PieceColor::White))
+//!     .put("f7", Piece::new(PieceType::Pawn, PieceColor::Black))
+//!     .put("g7", Piece::new(PieceType::Pawn, PieceColor::Black))
+//!     .put("h7", Piece::new(PieceType::Pawn, PieceColor::Black));
+//! let mut game = builder.validate().unwrap();
+//! assert_eq!(game.make_move("a1", "a8"), Some(GameState::GameOver));
+//! ```
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::fen::{self, find_king, opposite};
+use crate::variant::{Standard, Variant};
+use crate::{square_index, square_name, Game, Piece, PieceColor, PieceType};
+
+/// Why a position can not be played.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SetupError {
+    /// A square name that is not like "e4".
+    InvalidSquare(String),
+    MissingKing(PieceColor),
+    TooManyKings(PieceColor),
+    /// A pawn on the first or last rank.
+    PawnOnBackRank(String),
+    /// More pieces than the side starts the game with.
+    TooManyPieces(PieceColor),
+    /// More pawns than the side starts the game with.
+    TooManyPawns(PieceColor),
+    /// More promoted pieces than there are missing pawns.
+    TooManyPromotions(PieceColor),
+    /// A castling right without a king and rook on their back rank.
+    InvalidCastling(char),
+    /// An en passant square without a pawn that just moved past it.
+    InvalidEnPassant(String),
+    /// The side that just moved left its king in check.
+    OpponentInCheck,
+    /// Checks no move could have given, e.g. by three pieces.
+    ImpossibleCheck,
+    /// A position the rules of the variant can not load.
+    Unplayable,
+}
+
+impl fmt::Display for SetupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SetupError::InvalidSquare(square) => write!(f, "\"{}\" is not a square", square),
+            SetupError::MissingKing(color) => write!(f, "{:?} has no king", color),
+            SetupError::TooManyKings(color) => write!(f, "{:?} has more than one king", color),
+            SetupError::PawnOnBackRank(square) => write!(f, "pawn on the back rank on {}", square),
+            SetupError::TooManyPieces(color) => write!(f, "{:?} has too many pieces", color),
+            SetupError::TooManyPawns(color) => write!(f, "{:?} has too many pawns", color),
+            SetupError::TooManyPromotions(color) => write!(f, "{:?} has more promoted pieces than missing pawns", color),
+            SetupError::InvalidCastling(c) => write!(f, "no king and rook for castling right '{}'", c),
+            SetupError::InvalidEnPassant(square) => write!(f, "no pawn can be taken en passant on {}", square),
+            SetupError::OpponentInCheck => write!(f, "the side not to move is in check"),
+            SetupError::ImpossibleCheck => write!(f, "the check could not have been given by a move"),
+            SetupError::Unplayable => write!(f, "the position can not be played"),
+        }
+    }
+}
+
+impl std::error::Error for SetupError {}
+
+/// A position being set up. Starts out empty with white to move and no
+/// castling rights, and becomes a `Game` with `validate`.
+#[derive(Clone)]
+pub struct BoardBuilder {
+    board: Vec<Option<Piece>>,
+    turn: PieceColor,
+    castling: String,
+    en_passant: Option<usize>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    variant: Arc<dyn Variant>,
+    chess960: bool,
+    checks: [u32; 2],
+    pockets: [Vec<PieceType>; 2],
+    invalid_squares: Vec<String>,
+}
+
+impl BoardBuilder {
+    pub fn new() -> BoardBuilder {
+        BoardBuilder {
+            board: vec![None; 64],
+            turn: PieceColor::White,
+            castling: String::from("-"),
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            variant: Arc::new(Standard),
+            chess960: false,
+            checks: [0, 0],
+            pockets: [Vec::new(), Vec::new()],
+            invalid_squares: Vec::new(),
+        }
+    }
+
+    /// Starts from the current position of `game`, with its variant.
+    pub fn from_game(game: &Game) -> BoardBuilder {
+        BoardBuilder {
+            board: game.gameboard.clone(),
+            turn: game.turn,
+            castling: game.castling_field(true),
+            en_passant: game.en_passant,
+            halfmove_clock: game.halfmove_clock,
+            fullmove_number: game.fullmove_number,
+            variant: Arc::clone(&game.variant),
+            chess960: game.chess960,
+            checks: game.checks,
+            pockets: game.pockets.clone(),
+            invalid_squares: Vec::new(),
+        }
+    }
+
+    /// Puts `piece` on `square`, replacing what was there.
+    pub fn put(&mut self, square: &str, piece: Piece) -> &mut Self {
+        if let Some(pos) = self.square(square) {
+            self.board[pos] = Some(piece);
+        }
+        self
+    }
+
+    /// Removes the piece on `square`, if any.
+    pub fn remove(&mut self, square: &str) -> &mut Self {
+        if let Some(pos) = self.square(square) {
+            self.board[pos] = None;
+        }
+        self
+    }
+
+    /// Removes every piece.
+    pub fn clear(&mut self) -> &mut Self {
+        self.board = vec![None; 64];
+        self
+    }
+
+    /// The piece on `square`, None if it is empty or not a square.
+    pub fn piece_at(&self, square: &str) -> Option<&Piece> {
+        self.board.get(square_index(square)?)?.as_ref()
+    }
+
+    pub fn set_turn(&mut self, turn: PieceColor) -> &mut Self {
+        self.turn = turn;
+        self
+    }
+
+    /// Castling rights as in FEN, e.g. "KQkq", "-" or rook files like "HAha".
+    pub fn set_castling(&mut self, rights: &str) -> &mut Self {
+        self.castling = if rights.is_empty() { String::from("-") } else { rights.to_string() };
+        self
+    }
+
+    /// The square a pawn can be taken en passant on, e.g. "e3", or None.
+    pub fn set_en_passant(&mut self, square: Option<&str>) -> &mut Self {
+        self.en_passant = match square {
+            Some(square) => self.square(square),
+            None => None,
+        };
+        self
+    }
+
+    pub fn set_halfmove_clock(&mut self, halfmove_clock: u32) -> &mut Self {
+        self.halfmove_clock = halfmove_clock;
+        self
+    }
+
+    /// The number of the next full move. Numbers start at 1.
+    pub fn set_fullmove_number(&mut self, fullmove_number: u32) -> &mut Self {
+        self.fullmove_number = fullmove_number.max(1);
+        self
+    }
+
+    /// Plays the position by the rules of `variant` instead of standard chess.
+    pub fn set_variant(&mut self, variant: impl Variant + 'static) -> &mut Self {
+        self.variant = Arc::new(variant);
+        self
+    }
+
+    /// Checks that the position can be reached in a game and makes a game
+    /// from it, or tells the first problem found.
+    pub fn validate(&self) -> Result<Game, SetupError> {
+        if let Some(problem) = self.problems().into_iter().next() {
+            return Err(problem);
+        }
+        let game = match Game::load_fen(&self.fen(), self.chess960, Arc::clone(&self.variant)) {
+            Some(game) => game,
+            None => return Err(SetupError::Unplayable),
+        };
+        match check_problems(&game).into_iter().next() {
+            Some(problem) => Err(problem),
+            None => Ok(game),
+        }
+    }
+
+    /// Every problem with the position apart from checks.
+    fn problems(&self) -> Vec<SetupError> {
+        let mut problems: Vec<SetupError> = self
+            .invalid_squares
+            .iter()
+            .map(|square| SetupError::InvalidSquare(square.clone()))
+            .collect();
+
+        for color in [PieceColor::White, PieceColor::Black] {
+            let pieces: Vec<&Piece> = self.board.iter().flatten().filter(|piece| piece.piececolor == color).collect();
+            let count = |piecetype: PieceType| pieces.iter().filter(|piece| piece.piecetype == piecetype).count();
+            if self.variant.kings_required() && count(PieceType::King) == 0 {
+                problems.push(SetupError::MissingKing(color));
+            }
+            if self.variant.kings_required() && count(PieceType::King) > 1 {
+                problems.push(SetupError::TooManyKings(color));
+            }
+            if pieces.len() > self.start_pieces(color, None) {
+                problems.push(SetupError::TooManyPieces(color));
+            }
+
+            // every piece more than the side started with is a promoted pawn
+            let start_pawns = self.start_pieces(color, Some(PieceType::Pawn));
+            let promoted: usize = [PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight]
+                .into_iter()
+                .map(|piecetype| count(piecetype).saturating_sub(self.start_pieces(color, Some(piecetype))))
+                .sum();
+            if count(PieceType::Pawn) > start_pawns {
+                problems.push(SetupError::TooManyPawns(color));
+            } else if !self.variant.uses_pockets() && promoted > start_pawns - count(PieceType::Pawn) {
+                problems.push(SetupError::TooManyPromotions(color));
+            }
+        }
+
+        for (pos, piece) in self.board.iter().enumerate() {
+            let piece = match piece {
+                Some(piece) if piece.piecetype == PieceType::Pawn => piece,
+                _ => continue,
+            };
+            let first_row = match piece.piececolor {
+                PieceColor::White => 7,
+                PieceColor::Black => 0,
+            };
+            let allowed = pos / 8 == first_row && self.variant.pawns_on_first_rank();
+            if (pos / 8 == 0 || pos / 8 == 7) && !allowed {
+                problems.push(SetupError::PawnOnBackRank(square_name(pos)));
+            }
+        }
+
+        if self.castling != "-" {
+            for c in self.castling.chars() {
+                match fen::parse_castling(&self.board, &c.to_string()) {
+                    Some((rooks, _)) if !rooks.is_empty() => (),
+                    _ => problems.push(SetupError::InvalidCastling(c)),
+                }
+            }
+        }
+        if let Some(pos) = self.en_passant {
+            if !fen::en_passant_valid(&self.board, self.turn, pos) {
+                problems.push(SetupError::InvalidEnPassant(square_name(pos)));
+            }
+        }
+        problems
+    }
+
+    /// The index of `square`, remembering it for `validate` if it is not a
+    /// square name.
+    fn square(&mut self, square: &str) -> Option<usize> {
+        let pos = square_index(square);
+        if pos.is_none() {
+            self.invalid_squares.push(square.to_string());
+        }
+        pos
+    }
+
+    /// How many pieces, or pieces of `piecetype`, `color` starts with. Both
+    /// sides together when captured pieces come back from the pockets.
+    fn start_pieces(&self, color: PieceColor, piecetype: Option<PieceType>) -> usize {
+        let placement = self.variant.start_fen().split([' ', '[']).next().unwrap_or("");
+        placement
+            .chars()
+            .filter_map(fen::char_to_piece)
+            .filter(|piece| self.variant.uses_pockets() || piece.piececolor == color)
+            .filter(|piece| piecetype.is_none_or(|piecetype| piece.piecetype == piecetype))
+            .count()
+    }
+
+    fn fen(&self) -> String {
+        let mut fen = fen::write_placement(&self.board, self.variant.uses_pockets());
+        if self.variant.uses_pockets() {
+            fen.push_str(&fen::write_pockets(&self.pockets));
+        }
+        match self.turn {
+            PieceColor::White => fen.push_str(" w "),
+            PieceColor::Black => fen.push_str(" b "),
+        }
+        fen.push_str(&self.castling);
+        match self.en_passant {
+            Some(pos) => fen.push_str(&format!(" {}", square_name(pos))),
+            None => fen.push_str(" -"),
+        }
+        if let Some(limit) = self.variant.check_limit() {
+            let white = limit.saturating_sub(self.checks[0]);
+            let black = limit.saturating_sub(self.checks[1]);
+            fen.push_str(&format!(" {}+{}", white, black));
+        }
+        fen.push_str(&format!(" {} {}", self.halfmove_clock, self.fullmove_number));
+        fen
+    }
+}
+
+/// Problems with the checks in the position of `game`.
+fn check_problems(game: &Game) -> Vec<SetupError> {
+    let mut problems: Vec<SetupError> = Vec::new();
+    let (board, turn, variant) = (&game.gameboard, game.turn, game.variant());
+    if variant.king_attacked(game, board, opposite(turn)) {
+        problems.push(SetupError::OpponentInCheck);
+    }
+    if let Some(king) = find_king(board, turn) {
+        if variant.king_attacked(game, board, turn) {
+            let checkers = game.attackers(board, king, opposite(turn));
+            // two pawns or knights can not both give check with one move
+            let jumpers = checkers
+                .iter()
+                .filter(|pos| {
+                    matches!(&board[**pos], Some(piece) if piece.piecetype == PieceType::Pawn || piece.piecetype == PieceType::Knight)
+                })
+                .count();
+            // one move can not open a line and give check along the same line
+            let aligned = checkers.len() == 2 && in_line(checkers[0], checkers[1], king);
+            if checkers.len() > 2 || jumpers > 1 || aligned || !after_pawn_push(game, king, &checkers) {
+                problems.push(SetupError::ImpossibleCheck);
+            }
+        }
+    }
+    problems
+}
+
+/// Whether the checks fit a pawn that just moved two squares, if the en
+/// passant square says so. The pawn has to give the check itself or open
+/// the line for a single checker.
+fn after_pawn_push(game: &Game, king: usize, checkers: &[usize]) -> bool {
+    let (pawn, from) = match game.en_passant {
+        Some(pos) if game.turn == PieceColor::White => (pos + 8, pos - 8),
+        Some(pos) => (pos - 8, pos + 8),
+        None => return true,
+    };
+    if checkers.len() > 1 {
+        return false;
+    }
+    if checkers[0] == pawn {
+        return true;
+    }
+    let mut before = game.gameboard.clone();
+    before.swap(pawn, from);
+    game.attackers(&before, king, opposite(game.turn)).is_empty()
+}
+
+/// Whether the three squares are on one rank, file or diagonal.
+fn in_line(a: usize, b: usize, c: usize) -> bool {
+    let lines: [fn(usize) -> i32; 4] = [
+        |pos| (pos / 8) as i32,
+        |pos| (pos % 8) as i32,
+        |pos| (pos / 8) as i32 - (pos % 8) as i32,
+        |pos| (pos / 8 + pos % 8) as i32,
+    ];
+    lines.iter().any(|line| line(a) == line(b) && line(b) == line(c))
+}
+
+impl Game {
+    /// Every problem that makes the current position impossible to reach,
+    /// e.g. after editing `gameboard`. Empty if the position is fine. A pawn
+    /// waiting for `set_promotion` is not a problem.
+    pub fn validate_position(&self) -> Vec<SetupError> {
+        let mut problems = BoardBuilder::from_game(self).problems();
+        if let Some(pos) = self.promotion_pos {
+            problems.retain(|problem| *problem != SetupError::PawnOnBackRank(square_name(pos)));
+        }
+        problems.extend(check_problems(self));
+        problems
+    }
+}
+
+impl Default for BoardBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BoardBuilder, SetupError};
+    use crate::{Game, GameState, Piece, PieceColor, PieceType};
+
+    fn piece(piecetype: PieceType, piececolor: PieceColor) -> Piece {
+        Piece::new(piecetype, piececolor)
+    }
+
+    fn kings() -> BoardBuilder {
+        let mut builder = BoardBuilder::new();
+        builder
+            .put("e1", piece(PieceType::King, PieceColor::White))
+            .put("e8", piece(PieceType::King, PieceColor::Black));
+        builder
+    }
+
+    #[test]
+    fn builds_a_game() {
+        let mut builder = kings();
+        builder
+            .put("h1", piece(PieceType::Rook, PieceColor::White))
+            .put("d5", piece(PieceType::Pawn, PieceColor::Black))
+            .put("e5", piece(PieceType::Pawn, PieceColor::White))
+            .set_castling("K")
+            .set_en_passant(Some("d6"))
+            .set_fullmove_number(30);
+        let mut game = builder.validate().unwrap();
+        assert_eq!(game.to_fen(), "4k3/8/8/3pP3/8/8/8/4K2R w K d6 0 30");
+        assert_eq!(game.make_move("e5", "d6"), Some(GameState::InProgress));
+        assert!(game.gameboard[27].is_none());
+
+        let builder = BoardBuilder::from_game(&Game::new());
+        assert_eq!(builder.validate().unwrap().to_fen(), Game::new().to_fen());
+    }
+
+    #[test]
+    fn reports_errors() {
+        let mut builder = kings();
+        builder.remove("e8");
+        assert_eq!(builder.validate().err(), Some(SetupError::MissingKing(PieceColor::Black)));
+
+        let mut builder = kings();
+        builder.put("a1", piece(PieceType::King, PieceColor::White));
+        assert_eq!(builder.validate().err(), Some(SetupError::TooManyKings(PieceColor::White)));
+
+        let mut builder = kings();
+        builder.put("c8", piece(PieceType::Pawn, PieceColor::White));
+        assert_eq!(builder.validate().err(), Some(SetupError::PawnOnBackRank(String::from("c8"))));
+
+        let mut builder = kings();
+        for square in ["a3", "b3", "c3", "d3", "e3", "f3", "g3", "h3", "a4", "b4", "c4", "d4", "e4", "f4", "g4", "h4"] {
+            builder.put(square, piece(PieceType::Knight, PieceColor::White));
+        }
+        assert_eq!(builder.validate().err(), Some(SetupError::TooManyPieces(PieceColor::White)));
+
+        let mut builder = kings();
+        builder.set_castling("Q");
+        assert_eq!(builder.validate().err(), Some(SetupError::InvalidCastling('Q')));
+
+        let mut builder = kings();
+        builder.set_en_passant(Some("e6"));
+        assert_eq!(builder.validate().err(), Some(SetupError::InvalidEnPassant(String::from("e6"))));
+
+        let mut builder = kings();
+        builder.put("z9", piece(PieceType::Queen, PieceColor::White));
+        assert_eq!(builder.validate().err(), Some(SetupError::InvalidSquare(String::from("z9"))));
+    }
+
+    #[test]
+    fn reports_impossible_checks() {
+        let mut builder = kings();
+        builder.put("e2", piece(PieceType::Rook, PieceColor::White)).set_turn(PieceColor::Black);
+        assert_eq!(builder.validate().unwrap().get_game_state(), GameState::Check);
+        builder.set_turn(PieceColor::White);
+        assert_eq!(builder.validate().err(), Some(SetupError::OpponentInCheck));
+
+        let mut builder = kings();
+        builder
+            .put("d6", piece(PieceType::Knight, PieceColor::White))
+            .put("f6", piece(PieceType::Knight, PieceColor::White))
+            .set_turn(PieceColor::Black);
+        assert_eq!(builder.validate().err(), Some(SetupError::ImpossibleCheck));
+
+        // a discovered double check is fine, two checks along one line are not
+        let game = Game::from_fen("4k3/8/8/1B6/8/8/8/4R1K1 b - - 0 1").unwrap();
+        assert!(game.validate_position().is_empty());
+        let game = Game::from_fen("R3k2R/8/8/8/8/8/8/6K1 b - - 0 1").unwrap();
+        assert_eq!(game.validate_position(), vec![SetupError::ImpossibleCheck]);
+
+        // after a pawn moved two squares only the pawn or the line behind it can give check
+        let game = Game::from_fen("8/8/8/3k4/4P3/8/8/4K3 b - e3 0 1").unwrap();
+        assert!(game.validate_position().is_empty());
+        let game = Game::from_fen("8/8/8/1k6/4P3/8/8/1R2K3 b - e3 0 1").unwrap();
+        assert_eq!(game.validate_position(), vec![SetupError::ImpossibleCheck]);
+    }
+
+    #[test]
+    fn validate_position_lists_every_problem() {
+        assert!(Game::new().validate_position().is_empty());
+
+        let mut game = Game::new();
+        game.gameboard[0] = Some(piece(PieceType::Queen, PieceColor::White));
+        game.gameboard[4] = None;
+        assert_eq!(
+            game.validate_position(),
+            vec![
+                SetupError::TooManyPieces(PieceColor::White),
+                SetupError::TooManyPromotions(PieceColor::White),
+                SetupError::MissingKing(PieceColor::Black),
+                SetupError::InvalidCastling('h'),
+                SetupError::InvalidCastling('a'),
+            ]
+        );
+
+        let mut game = Game::from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        game.make_move("b7", "b8");
+        assert!(game.validate_position().is_empty());
+    }
+}
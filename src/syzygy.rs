@@ -0,0 +1,1232 @@
+//! Probing Syzygy endgame tablebases (`.rtbw` and `.rtbz` files).
+//!
+//! A WDL table tells if the side to move wins, draws or loses, and a DTZ
+//! table how many plies it takes until the next capture or pawn move (the
+//! "distance to zero") with the best play. Tables are read from a directory
+//! the first time a position needs them.
+//!
+//! ```
+//! use erikrub_chess_lib::syzygy::{Tablebase, Wdl};
+//! use erikrub_chess_lib::Game;
+//!
+//! let tablebase = Tablebase::open(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/syzygy")).unwrap();
+//! let game = Game::from_fen("8/8/8/8/8/2k5/8/K2Q4 w - - 0 1").unwrap();
+//! assert_eq!(tablebase.probe_wdl(&game), Some(Wdl::Win));
+//! assert!(tablebase.best_move(&game).is_some());
+//! ```
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::movegen::MoveList;
+use crate::variant::is_capture;
+use crate::{Game, Move, Piece, PieceColor, PieceType};
+
+const WDL_MAGIC: [u8; 4] = [0x71, 0xe8, 0x23, 0x5d];
+const DTZ_MAGIC: [u8; 4] = [0xd7, 0x66, 0x0c, 0xa5];
+
+/// The most pieces a table can have.
+const MAX_PIECES: usize = 7;
+
+// table flags, the first four only in DTZ tables
+const STM: u8 = 1;
+const MAPPED: u8 = 2;
+const WIN_PLIES: u8 = 4;
+const LOSS_PLIES: u8 = 8;
+const WIDE: u8 = 16;
+const SINGLE_VALUE: u8 = 128;
+
+/// Win, draw or loss for the side to move. A cursed win is a win that the
+/// 50 move rule turns into a draw, and a blessed loss the other way round.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Wdl {
+    Loss,
+    BlessedLoss,
+    Draw,
+    CursedWin,
+    Win,
+}
+
+impl Wdl {
+    fn from_value(value: i32) -> Option<Wdl> {
+        match value {
+            -2 => Some(Wdl::Loss),
+            -1 => Some(Wdl::BlessedLoss),
+            0 => Some(Wdl::Draw),
+            1 => Some(Wdl::CursedWin),
+            2 => Some(Wdl::Win),
+            _ => None,
+        }
+    }
+
+    fn value(self) -> i32 {
+        self as i32 - 2
+    }
+
+    /// The result for the other side.
+    fn opposite(self) -> Wdl {
+        Wdl::from_value(-self.value()).unwrap()
+    }
+}
+
+/// The tables in a directory, each loaded when first needed.
+pub struct Tablebase {
+    dir: PathBuf,
+    wdl: HashMap<String, OnceLock<Option<Table>>>,
+    dtz: HashMap<String, OnceLock<Option<Table>>>,
+    max_pieces: usize,
+}
+
+impl Tablebase {
+    /// Finds the tables in `dir`, named after their material like
+    /// "KQvK.rtbw". None if the directory can not be read.
+    pub fn open(dir: impl AsRef<Path>) -> Option<Tablebase> {
+        let mut tablebase = Tablebase {
+            dir: dir.as_ref().to_path_buf(),
+            wdl: HashMap::new(),
+            dtz: HashMap::new(),
+            max_pieces: 0,
+        };
+        for entry in fs::read_dir(dir).ok()?.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let (material, tables) = match name.split_once('.') {
+                Some((material, "rtbw")) => (material, &mut tablebase.wdl),
+                Some((material, "rtbz")) => (material, &mut tablebase.dtz),
+                _ => continue,
+            };
+            if let Some(info) = Material::parse(material) {
+                tablebase.max_pieces = tablebase.max_pieces.max(info.count);
+                tables.insert(material.to_string(), OnceLock::new());
+            }
+        }
+        Some(tablebase)
+    }
+
+    /// The most pieces, kings included, of a table that was found.
+    pub fn max_pieces(&self) -> usize {
+        self.max_pieces
+    }
+
+    /// Win, draw or loss for the side to move. None if the game is not
+    /// standard chess, there are castling rights or a table is missing.
+    pub fn probe_wdl(&self, game: &Game) -> Option<Wdl> {
+        let mut game = self.probe_game(game)?;
+        Some(self.search(&mut game, false)?.0)
+    }
+
+    /// Plies to the next capture or pawn move with the best play, positive
+    /// when the side to move wins and negative when it loses. Cursed wins
+    /// and blessed losses are counted 100 plies further away, and draws are
+    /// 0. None as for `probe_wdl`.
+    pub fn probe_dtz(&self, game: &Game) -> Option<i32> {
+        let mut game = self.probe_game(game)?;
+        self.dtz(&mut game)
+    }
+
+    /// The best move according to the tables: the win that reaches the next
+    /// capture or pawn move soonest, or the loss that puts it off longest.
+    /// A mate is always best. None as for `probe_wdl`, or if there is no
+    /// move.
+    pub fn best_move(&self, game: &Game) -> Option<Move> {
+        let mut game = self.probe_game(game)?;
+        let mut moves = MoveList::new();
+        game.generate_moves(&mut moves);
+        let mut best: Option<(Move, i32)> = None;
+        for _move in &moves {
+            let undo = game.make_move_unchecked(_move);
+            let score = if game.is_check() && !has_moves(&game) {
+                Some(i32::MAX)
+            } else if game.halfmove_clock == 0 {
+                let wdl = self.search(&mut game, false).map(|(wdl, _)| wdl.opposite());
+                wdl.map(|wdl| rank(dtz_before_zeroing(wdl)))
+            } else {
+                self.dtz(&mut game).map(|dtz| rank(-dtz - dtz.signum()))
+            };
+            game.unmake_move(_move, undo);
+            let score = score?;
+            if best.is_none_or(|(_, best)| score > best) {
+                best = Some((*_move, score));
+            }
+        }
+        best.map(|(_move, _)| _move)
+    }
+
+    /// A copy of the game to search in, if the tables can be used for it.
+    fn probe_game(&self, game: &Game) -> Option<Game> {
+        let pieces = game.gameboard.iter().flatten().count();
+        if game.variant.name() != "Standard"
+            || !game.castling.is_empty()
+            || game.promotion_pos.is_some()
+            || pieces > self.max_pieces.max(2)
+        {
+            return None;
+        }
+        Some(game.clone())
+    }
+
+    /// The table for the material on the board, and if it is stored with
+    /// the colors the other way round.
+    fn table(&self, game: &Game, dtz: bool) -> Option<(&Table, bool)> {
+        let tables = if dtz { &self.dtz } else { &self.wdl };
+        let white = material_name(&game.gameboard, PieceColor::White);
+        let black = material_name(&game.gameboard, PieceColor::Black);
+        let (name, flipped) = match format!("{}v{}", white, black) {
+            name if tables.contains_key(&name) => (name, false),
+            _ => (format!("{}v{}", black, white), true),
+        };
+        let table = tables.get(&name)?.get_or_init(|| {
+            let extension = if dtz { "rtbz" } else { "rtbw" };
+            let bytes = fs::read(self.dir.join(format!("{}.{}", name, extension))).ok()?;
+            Table::parse(bytes, &name, dtz)
+        });
+        Some((table.as_ref()?, flipped))
+    }
+
+    /// The stored result, which does not know about en passant captures.
+    fn probe_wdl_table(&self, game: &Game) -> Option<Wdl> {
+        if game.gameboard.iter().flatten().count() == 2 {
+            return Some(Wdl::Draw);
+        }
+        let (table, flipped) = self.table(game, false)?;
+        let (which, _, index) = table.index(&game.gameboard, game.turn, flipped)?;
+        Wdl::from_value(table.decompress(which, index)? as i32 - 2)
+    }
+
+    /// The stored DTZ of a position won or lost as `wdl`. Some(None) if the
+    /// table only has the positions with the other side to move.
+    fn probe_dtz_table(&self, game: &Game, wdl: Wdl) -> Option<Option<i32>> {
+        let (table, flipped) = self.table(game, true)?;
+        let (which, stm, index) = table.index(&game.gameboard, game.turn, flipped)?;
+        let flags = table.pairs[which].flags;
+        let both_sides = table.material.symmetric && !table.material.has_pawns;
+        if (flags & STM) as usize != stm && !both_sides {
+            return Some(None);
+        }
+        let value = table.decompress(which, index)?;
+        Some(Some(table.map_score(which, value as usize, wdl)?))
+    }
+
+    /// The result for the side to move, trying the captures first, and
+    /// with `zeroing` the pawn moves too. Also tells if the best move found
+    /// that way is a capture or pawn move, for DTZ.
+    fn search(&self, game: &mut Game, zeroing: bool) -> Option<(Wdl, bool)> {
+        let mut moves = MoveList::new();
+        game.generate_moves(&mut moves);
+        let mut best = Wdl::Loss;
+        let mut searched = 0;
+        for _move in &moves {
+            let pawn = matches!(&game.gameboard[_move.from], Some(piece) if piece.piecetype == PieceType::Pawn);
+            if !(is_capture(game, _move) || zeroing && pawn) {
+                continue;
+            }
+            searched += 1;
+            let undo = game.make_move_unchecked(_move);
+            let value = self.search(game, false);
+            game.unmake_move(_move, undo);
+            let value = value?.0.opposite();
+            if value > best {
+                best = value;
+                if value == Wdl::Win {
+                    return Some((value, true));
+                }
+            }
+        }
+
+        // With only captures every move has been tried. The table could be
+        // wrong here, e.g. it does not know about en passant.
+        let all_searched = searched > 0 && searched == moves.len();
+        let value = if all_searched { best } else { self.probe_wdl_table(game)? };
+        if best >= value {
+            return Some((best, best > Wdl::Draw || all_searched));
+        }
+        Some((value, false))
+    }
+
+    fn dtz(&self, game: &mut Game) -> Option<i32> {
+        let (wdl, zeroing) = self.search(game, true)?;
+        if wdl == Wdl::Draw {
+            return Some(0);
+        }
+        if zeroing {
+            return Some(dtz_before_zeroing(wdl));
+        }
+        let cursed = matches!(wdl, Wdl::CursedWin | Wdl::BlessedLoss);
+        if let Some(dtz) = self.probe_dtz_table(game, wdl)? {
+            return Some((dtz + if cursed { 100 } else { 0 }) * wdl.value().signum());
+        }
+
+        // The table has the other side to move, so look one move ahead.
+        let mut moves = MoveList::new();
+        game.generate_moves(&mut moves);
+        let mut min = i32::MAX;
+        for _move in &moves {
+            let pawn = matches!(&game.gameboard[_move.from], Some(piece) if piece.piecetype == PieceType::Pawn);
+            let zeroing = pawn || is_capture(game, _move);
+            let undo = game.make_move_unchecked(_move);
+            let dtz = if zeroing {
+                self.search(game, false).map(|(wdl, _)| -dtz_before_zeroing(wdl))
+            } else {
+                self.dtz(game).map(|dtz| -dtz)
+            };
+            let mates = game.is_check() && !has_moves(game);
+            game.unmake_move(_move, undo);
+            let mut dtz = dtz?;
+            if dtz == 1 && mates {
+                min = 1;
+            }
+            if !zeroing {
+                dtz += dtz.signum();
+            }
+            if dtz < min && dtz.signum() == wdl.value().signum() {
+                min = dtz;
+            }
+        }
+        Some(if min == i32::MAX { -1 } else { min })
+    }
+}
+
+/// DTZ when the best move is a capture or pawn move.
+fn dtz_before_zeroing(wdl: Wdl) -> i32 {
+    match wdl {
+        Wdl::Win => 1,
+        Wdl::CursedWin => 101,
+        Wdl::Draw => 0,
+        Wdl::BlessedLoss => -101,
+        Wdl::Loss => -1,
+    }
+}
+
+/// Orders the DTZ after a move: quick wins first, draws, slow losses.
+fn rank(dtz: i32) -> i32 {
+    match dtz {
+        0 => 0,
+        dtz if dtz > 0 => 10_000 - dtz,
+        dtz => -10_000 - dtz,
+    }
+}
+
+fn has_moves(game: &Game) -> bool {
+    let mut moves = MoveList::new();
+    game.generate_moves(&mut moves);
+    !moves.is_empty()
+}
+
+/// The pieces of `color` as in table names, e.g. "KRP".
+fn material_name(board: &[Option<Piece>], color: PieceColor) -> String {
+    let mut name = String::new();
+    for (piecetype, letter) in PIECE_LETTERS {
+        let count = board
+            .iter()
+            .flatten()
+            .filter(|piece| piece.piecetype == piecetype && piece.piececolor == color)
+            .count();
+        name.extend(std::iter::repeat_n(letter, count));
+    }
+    name
+}
+
+const PIECE_LETTERS: [(PieceType, char); 6] = [
+    (PieceType::King, 'K'),
+    (PieceType::Queen, 'Q'),
+    (PieceType::Rook, 'R'),
+    (PieceType::Bishop, 'B'),
+    (PieceType::Knight, 'N'),
+    (PieceType::Pawn, 'P'),
+];
+
+/// The code of a piece in table files, 1-6 for white pawn to king and 9-14
+/// for black.
+fn piece_code(piece: &Piece) -> u8 {
+    let code = match piece.piecetype {
+        PieceType::Pawn => 1,
+        PieceType::Knight => 2,
+        PieceType::Bishop => 3,
+        PieceType::Rook => 4,
+        PieceType::Queen => 5,
+        PieceType::King => 6,
+    };
+    match piece.piececolor {
+        PieceColor::White => code,
+        PieceColor::Black => code + 8,
+    }
+}
+
+/// Square numbers in the files start at a1 and go along the ranks, while
+/// `gameboard` starts at a8. The same sum turns them into each other.
+fn flip_board_square(pos: usize) -> usize {
+    (7 - pos / 8) * 8 + pos % 8
+}
+
+fn file_of(square: usize) -> usize {
+    square % 8
+}
+
+fn rank_of(square: usize) -> usize {
+    square / 8
+}
+
+/// Negative below the a1-h8 diagonal, 0 on it.
+fn off_diagonal(square: usize) -> i32 {
+    rank_of(square) as i32 - file_of(square) as i32
+}
+
+/// The material of a table, from its name.
+#[derive(Clone, Debug)]
+struct Material {
+    count: usize,
+    has_pawns: bool,
+    /// Some side has exactly one piece of a kind other than the king.
+    unique: bool,
+    /// Pawns of the leading color and of the other one.
+    pawns: [usize; 2],
+    /// Both sides have the same pieces.
+    symmetric: bool,
+}
+
+impl Material {
+    fn parse(name: &str) -> Option<Material> {
+        let (white, black) = name.split_once('v')?;
+        let valid = |side: &str| {
+            side.starts_with('K')
+                && side.chars().filter(|letter| *letter == 'K').count() == 1
+                && side.chars().all(|letter| "KQRBNP".contains(letter))
+        };
+        if !valid(white) || !valid(black) || white.len() + black.len() > MAX_PIECES {
+            return None;
+        }
+        let count = |side: &str, letter: char| side.chars().filter(|l| *l == letter).count();
+        let unique = "QRBNP".chars().any(|letter| count(white, letter) == 1 || count(black, letter) == 1);
+        let (white_pawns, black_pawns) = (count(white, 'P'), count(black, 'P'));
+        // The color with fewer pawns leads, as that packs better.
+        let white_leads = black_pawns == 0 || (white_pawns > 0 && black_pawns >= white_pawns);
+        Some(Material {
+            count: white.len() + black.len(),
+            has_pawns: white_pawns + black_pawns > 0,
+            unique,
+            pawns: if white_leads { [white_pawns, black_pawns] } else { [black_pawns, white_pawns] },
+            symmetric: white == black,
+        })
+    }
+}
+
+/// Tables used to turn positions into indexes, the same for every file.
+struct Indexes {
+    /// The squares below the a1-h8 diagonal, 0..28.
+    b1h1h7: [usize; 64],
+    /// The a1-d1-d4 triangle, 0..10 with the diagonal last.
+    a1d1d4: [usize; 64],
+    /// The 462 ways to put two kings with the first in the triangle.
+    kk: [[usize; 64]; 10],
+    binomial: [[u64; 64]; MAX_PIECES],
+    /// a2-h7 to 0..48, most for the pawns nearest the edge and lowest.
+    pawns: [usize; 64],
+    lead_pawn_index: [[u64; 64]; 6],
+    lead_pawns_size: [[u64; 4]; 6],
+}
+
+fn indexes() -> &'static Indexes {
+    static INDEXES: OnceLock<Indexes> = OnceLock::new();
+    INDEXES.get_or_init(|| {
+        let mut indexes = Indexes {
+            b1h1h7: [0; 64],
+            a1d1d4: [0; 64],
+            kk: [[0; 64]; 10],
+            binomial: [[0; 64]; MAX_PIECES],
+            pawns: [0; 64],
+            lead_pawn_index: [[0; 64]; 6],
+            lead_pawns_size: [[0; 4]; 6],
+        };
+        let mut code = 0;
+        for square in 0..64 {
+            if off_diagonal(square) < 0 {
+                indexes.b1h1h7[square] = code;
+                code += 1;
+            }
+        }
+
+        let mut diagonal: Vec<usize> = Vec::new();
+        code = 0;
+        for square in 0..28 {
+            if off_diagonal(square) < 0 && file_of(square) <= 3 {
+                indexes.a1d1d4[square] = code;
+                code += 1;
+            } else if off_diagonal(square) == 0 && file_of(square) <= 3 {
+                diagonal.push(square);
+            }
+        }
+        for square in diagonal {
+            indexes.a1d1d4[square] = code;
+            code += 1;
+        }
+
+        // With the first king on the diagonal, the other may not be above it.
+        let mut both_on_diagonal: Vec<(usize, usize)> = Vec::new();
+        code = 0;
+        for index in 0..10 {
+            for first in 0..28 {
+                if indexes.a1d1d4[first] != index || (index == 0 && first != 1) {
+                    continue;
+                }
+                for second in 0..64 {
+                    let adjacent = file_of(first).abs_diff(file_of(second)) <= 1
+                        && rank_of(first).abs_diff(rank_of(second)) <= 1;
+                    if adjacent || (off_diagonal(first) == 0 && off_diagonal(second) > 0) {
+                        continue;
+                    }
+                    if off_diagonal(first) == 0 && off_diagonal(second) == 0 {
+                        both_on_diagonal.push((index, second));
+                    } else {
+                        indexes.kk[index][second] = code;
+                        code += 1;
+                    }
+                }
+            }
+        }
+        for (index, second) in both_on_diagonal {
+            indexes.kk[index][second] = code;
+            code += 1;
+        }
+
+        indexes.binomial[0][0] = 1;
+        for n in 1..64 {
+            for k in 0..MAX_PIECES.min(n + 1) {
+                indexes.binomial[k][n] = if k > 0 { indexes.binomial[k - 1][n - 1] } else { 0 }
+                    + if k < n { indexes.binomial[k][n - 1] } else { 0 };
+            }
+        }
+
+        let mut available = 47;
+        for count in 1..6 {
+            for file in 0..4 {
+                let mut index = 0;
+                for rank in 1..7 {
+                    let square = rank * 8 + file;
+                    if count == 1 {
+                        indexes.pawns[square] = available;
+                        indexes.pawns[square ^ 7] = available - 1;
+                        available = available.saturating_sub(2);
+                    }
+                    indexes.lead_pawn_index[count][square] = index;
+                    index += indexes.binomial[count - 1][indexes.pawns[square]];
+                }
+                indexes.lead_pawns_size[count][file] = index;
+            }
+        }
+        indexes
+    })
+}
+
+/// How the values of one table (a side to move and, with pawns, a file of
+/// the leading pawn) are stored.
+#[derive(Clone, Debug, Default)]
+struct Pairs {
+    flags: u8,
+    /// The order of the pieces in the index.
+    pieces: [u8; MAX_PIECES],
+    group_len: [usize; MAX_PIECES + 1],
+    group_index: [u64; MAX_PIECES + 1],
+    block_size: usize,
+    span: u64,
+    blocks: usize,
+    block_lengths: usize,
+    sparse_entries: usize,
+    /// The shortest code, or the value of a table with a single value.
+    min_len: usize,
+    lowest_sym: usize,
+    base: Vec<u64>,
+    symlen: Vec<u8>,
+    btree: usize,
+    sparse_index: usize,
+    block_length: usize,
+    data: usize,
+    map: [usize; 4],
+}
+
+impl Pairs {
+    /// Groups the pieces. The index is the sum of each group's index times
+    /// the ways to place the groups before it. `order` tells where the
+    /// leading group and the other pawns come.
+    fn set_groups(&mut self, material: &Material, order: [usize; 2], file: usize) {
+        let indexes = indexes();
+        let mut n = 0;
+        let mut first_len: i32 = if material.has_pawns { 0 } else if material.unique { 3 } else { 2 };
+        self.group_len[0] = 1;
+        for i in 1..material.count {
+            first_len -= 1;
+            if first_len > 0 || self.pieces[i] == self.pieces[i - 1] {
+                self.group_len[n] += 1;
+            } else {
+                n += 1;
+                self.group_len[n] = 1;
+            }
+        }
+        n += 1;
+        self.group_len[n] = 0;
+
+        let both_pawns = material.has_pawns && material.pawns[1] > 0;
+        let mut next = if both_pawns { 2 } else { 1 };
+        let mut free = 64 - self.group_len[0] - if both_pawns { self.group_len[1] } else { 0 };
+        let mut index: u64 = 1;
+        let mut k = 0;
+        while next < n || k == order[0] || k == order[1] {
+            if k == order[0] {
+                self.group_index[0] = index;
+                index *= if material.has_pawns {
+                    indexes.lead_pawns_size[self.group_len[0]][file]
+                } else if material.unique {
+                    31332
+                } else {
+                    462
+                };
+            } else if k == order[1] {
+                self.group_index[1] = index;
+                index *= indexes.binomial[self.group_len[1]][48 - self.group_len[0]];
+            } else {
+                self.group_index[next] = index;
+                index *= indexes.binomial[self.group_len[next]][free];
+                free -= self.group_len[next];
+                next += 1;
+            }
+            k += 1;
+        }
+        self.group_index[n] = index;
+    }
+
+    /// How many values the table has.
+    fn size(&self) -> u64 {
+        let groups = self.group_len.iter().position(|len| *len == 0).unwrap_or(0);
+        self.group_index[groups]
+    }
+}
+
+/// A WDL or DTZ table read from a file.
+struct Table {
+    bytes: Vec<u8>,
+    name: String,
+    material: Material,
+    /// 4 files with pawns, else 1.
+    files: usize,
+    /// By file and side to move, as in the file. DTZ tables have one side.
+    pairs: Vec<Pairs>,
+}
+
+/// Reads little-endian numbers, None past the end.
+fn read_le(bytes: &[u8], pos: usize, len: usize) -> Option<u64> {
+    let slice = bytes.get(pos..pos.checked_add(len)?)?;
+    Some(slice.iter().rev().fold(0, |number, byte| number << 8 | *byte as u64))
+}
+
+/// Reads big-endian numbers, with zeros past the end.
+fn read_be(bytes: &[u8], pos: usize, len: usize) -> u64 {
+    (0..len).fold(0, |number, i| number << 8 | *bytes.get(pos + i).unwrap_or(&0) as u64)
+}
+
+impl Table {
+    /// Reads the layout of a table named like "KQvK". None if the file
+    /// does not look like such a table.
+    fn parse(bytes: Vec<u8>, name: &str, dtz: bool) -> Option<Table> {
+        let magic = if dtz { DTZ_MAGIC } else { WDL_MAGIC };
+        if bytes.get(0..4)? != magic {
+            return None;
+        }
+        let material = Material::parse(name)?;
+        let mut table = Table::new(name, material, dtz);
+        let sides = table.pairs.len() / table.files;
+        let both_pawns = table.material.has_pawns && table.material.pawns[1] > 0;
+
+        let flags = *bytes.get(4)?;
+        if (flags & 2 != 0) != table.material.has_pawns {
+            return None;
+        }
+        let mut pos = 5;
+        for file in 0..table.files {
+            let orders = [
+                [(bytes.get(pos)? & 0xf) as usize, if both_pawns { (bytes.get(pos + 1)? & 0xf) as usize } else { 0xf }],
+                [(bytes.get(pos)? >> 4) as usize, if both_pawns { (bytes.get(pos + 1)? >> 4) as usize } else { 0xf }],
+            ];
+            pos += 1 + both_pawns as usize;
+            for k in 0..table.material.count {
+                let byte = *bytes.get(pos)?;
+                for side in 0..sides {
+                    table.pairs[file * sides + side].pieces[k] = if side == 0 { byte & 0xf } else { byte >> 4 };
+                }
+                pos += 1;
+            }
+            for (side, order) in orders.iter().enumerate().take(sides) {
+                let material = table.material.clone();
+                table.pairs[file * sides + side].set_groups(&material, *order, file);
+            }
+        }
+        pos += pos & 1;
+
+        for pairs in table.pairs.iter_mut() {
+            pos = pairs.set_sizes(&bytes, pos)?;
+        }
+        if dtz {
+            for file in 0..table.files {
+                let pairs = &mut table.pairs[file];
+                if pairs.flags & MAPPED == 0 {
+                    continue;
+                }
+                if pairs.flags & WIDE != 0 {
+                    pos += pos & 1;
+                    for i in 0..4 {
+                        pairs.map[i] = pos;
+                        pos += 2 * read_le(&bytes, pos, 2)? as usize + 2;
+                    }
+                } else {
+                    for i in 0..4 {
+                        pairs.map[i] = pos;
+                        pos += *bytes.get(pos)? as usize + 1;
+                    }
+                }
+            }
+            pos += pos & 1;
+        }
+        for pairs in table.pairs.iter_mut() {
+            pairs.sparse_index = pos;
+            pos += pairs.sparse_entries * 6;
+        }
+        for pairs in table.pairs.iter_mut() {
+            pairs.block_length = pos;
+            pos += pairs.block_lengths * 2;
+        }
+        for pairs in table.pairs.iter_mut() {
+            pos = (pos + 0x3f) & !0x3f;
+            pairs.data = pos;
+            pos += pairs.blocks * pairs.block_size;
+        }
+        if pos > bytes.len() {
+            return None;
+        }
+        table.bytes = bytes;
+        Some(table)
+    }
+
+    /// A table without values, for the layout of the pieces.
+    fn new(name: &str, material: Material, dtz: bool) -> Table {
+        let files = if material.has_pawns { 4 } else { 1 };
+        let sides = if !dtz && !material.symmetric { 2 } else { 1 };
+        Table {
+            bytes: Vec::new(),
+            name: name.to_string(),
+            files,
+            pairs: vec![Pairs::default(); sides * files],
+            material,
+        }
+    }
+
+    /// Which of `pairs`, the side to move in the table and the index of the
+    /// position on `board`. With `flipped` the table has the colors the
+    /// other way round.
+    fn index(&self, board: &[Option<Piece>], turn: PieceColor, flipped: bool) -> Option<(usize, usize, u64)> {
+        let indexes = indexes();
+        let symmetric_black = self.material.symmetric && turn == PieceColor::Black;
+        let flip = symmetric_black || flipped;
+        let (flip_color, flip_squares) = if flip { (8, 56) } else { (0, 0) };
+        let stm = flip as usize ^ (turn == PieceColor::Black) as usize;
+
+        let mut squares = [0; MAX_PIECES];
+        let mut pieces = [0; MAX_PIECES];
+        let mut size = 0;
+        let mut lead_pawns = 0;
+        let mut file = 0;
+        let lead_code = self.pairs[0].pieces[0] ^ flip_color;
+        if self.material.has_pawns {
+            for square in 0..64 {
+                if matches!(&board[flip_board_square(square)], Some(piece) if piece_code(piece) == lead_code) {
+                    *squares.get_mut(size)? = square ^ flip_squares;
+                    size += 1;
+                }
+            }
+            lead_pawns = size;
+            let mut lead = 0;
+            for i in 1..lead_pawns {
+                if indexes.pawns[squares[i]] > indexes.pawns[squares[lead]] {
+                    lead = i;
+                }
+            }
+            squares.swap(0, lead);
+            file = file_of(squares[0]).min(7 - file_of(squares[0]));
+        }
+
+        for square in 0..64 {
+            if let Some(piece) = &board[flip_board_square(square)] {
+                if self.material.has_pawns && piece_code(piece) == lead_code {
+                    continue;
+                }
+                *squares.get_mut(size)? = square ^ flip_squares;
+                pieces[size] = piece_code(piece) ^ flip_color;
+                size += 1;
+            }
+        }
+        if size != self.material.count {
+            return None;
+        }
+
+        let sides = self.pairs.len() / self.files;
+        let which = file * sides + stm % sides;
+        let pairs = &self.pairs[which];
+        for i in lead_pawns..size.saturating_sub(1) {
+            for j in i + 1..size {
+                if pairs.pieces[i] == pieces[j] {
+                    pieces.swap(i, j);
+                    squares.swap(i, j);
+                    break;
+                }
+            }
+        }
+
+        if file_of(squares[0]) > 3 {
+            for square in squares.iter_mut().take(size) {
+                *square ^= 7;
+            }
+        }
+
+        let mut index: u64;
+        if self.material.has_pawns {
+            index = indexes.lead_pawn_index.get(lead_pawns)?[squares[0]];
+            squares[1..lead_pawns].sort_by_key(|square| indexes.pawns[*square]);
+            for (i, square) in squares.iter().enumerate().take(lead_pawns).skip(1) {
+                index += indexes.binomial[i][indexes.pawns[*square]];
+            }
+        } else {
+            if rank_of(squares[0]) > 3 {
+                for square in squares.iter_mut().take(size) {
+                    *square ^= 56;
+                }
+            }
+            for i in 0..pairs.group_len[0] {
+                if off_diagonal(squares[i]) == 0 {
+                    continue;
+                }
+                if off_diagonal(squares[i]) > 0 {
+                    for square in squares.iter_mut().take(size).skip(i) {
+                        *square = ((*square >> 3) | (*square << 3)) & 63;
+                    }
+                }
+                break;
+            }
+
+            index = if self.material.unique {
+                let adjust1 = (squares[1] > squares[0]) as u64;
+                let adjust2 = (squares[2] > squares[0]) as u64 + (squares[2] > squares[1]) as u64;
+                let (s1, s2) = (squares[1] as u64, squares[2] as u64);
+                let (r0, r1, r2) = (rank_of(squares[0]) as u64, rank_of(squares[1]) as u64, rank_of(squares[2]) as u64);
+                if off_diagonal(squares[0]) != 0 {
+                    (indexes.a1d1d4[squares[0]] as u64 * 63 + (s1 - adjust1)) * 62 + s2 - adjust2
+                } else if off_diagonal(squares[1]) != 0 {
+                    (6 * 63 + r0 * 28 + indexes.b1h1h7[squares[1]] as u64) * 62 + s2 - adjust2
+                } else if off_diagonal(squares[2]) != 0 {
+                    6 * 63 * 62 + 4 * 28 * 62 + r0 * 7 * 28 + (r1 - adjust1) * 28 + indexes.b1h1h7[squares[2]] as u64
+                } else {
+                    6 * 63 * 62 + 4 * 28 * 62 + 4 * 7 * 28 + r0 * 6 * 7 + (r1 - adjust1) * 6 + (r2 - adjust2)
+                }
+            } else {
+                indexes.kk[indexes.a1d1d4[squares[0]]][squares[1]] as u64
+            };
+        }
+
+        index *= pairs.group_index[0];
+        let mut start = pairs.group_len[0];
+        let mut remaining_pawns = self.material.has_pawns && self.material.pawns[1] > 0;
+        let mut next = 1;
+        while pairs.group_len[next] != 0 {
+            let end = start + pairs.group_len[next];
+            squares[start..end].sort();
+            let mut n = 0;
+            for i in start..end {
+                let adjust = squares[..start].iter().filter(|square| squares[i] > **square).count();
+                let free = squares[i].checked_sub(adjust + if remaining_pawns { 8 } else { 0 })?;
+                n += indexes.binomial.get(i - start + 1)?[free];
+            }
+            remaining_pawns = false;
+            index += n * pairs.group_index[next];
+            start = end;
+            next += 1;
+        }
+        Some((which, stm, index))
+    }
+
+    /// The value stored at `index` in `self.pairs[which]`.
+    fn decompress(&self, which: usize, index: u64) -> Option<u16> {
+        let pairs = &self.pairs[which];
+        let bytes = &self.bytes;
+        if pairs.flags & SINGLE_VALUE != 0 {
+            return Some(pairs.min_len as u16);
+        }
+        if index >= pairs.size() {
+            return None;
+        }
+        let block_length = |block: i64| -> Option<i64> {
+            Some(read_le(bytes, pairs.block_length + 2 * usize::try_from(block).ok()?, 2)? as i64)
+        };
+
+        // The sparse index points into the middle of every span of values.
+        let k = (index / pairs.span) as usize;
+        let mut block = read_le(bytes, pairs.sparse_index + 6 * k, 4)? as i64;
+        let mut offset = read_le(bytes, pairs.sparse_index + 6 * k + 4, 2)? as i64;
+        offset += (index % pairs.span) as i64 - (pairs.span / 2) as i64;
+        while offset < 0 {
+            block -= 1;
+            offset += block_length(block)? + 1;
+        }
+        while offset > block_length(block)? {
+            offset -= block_length(block)? + 1;
+            block += 1;
+        }
+
+        // Read canonical Huffman codes until the one that holds the value.
+        let mut pos = pairs.data + block as usize * pairs.block_size;
+        let mut buffer = read_be(bytes, pos, 8);
+        pos += 8;
+        let mut bits = 64;
+        let mut sym;
+        loop {
+            let mut len = 0;
+            while buffer < *pairs.base.get(len)? {
+                len += 1;
+            }
+            let code = (buffer - pairs.base[len]).checked_shr((64 - len - pairs.min_len) as u32).unwrap_or(0);
+            sym = code as usize + read_le(bytes, pairs.lowest_sym + 2 * len, 2)? as usize;
+            let sym_len = *pairs.symlen.get(sym)? as i64;
+            if offset < sym_len + 1 {
+                break;
+            }
+            offset -= sym_len + 1;
+            len += pairs.min_len;
+            buffer = buffer.checked_shl(len as u32).unwrap_or(0);
+            bits -= len as i32;
+            if bits <= 32 {
+                bits += 32;
+                buffer |= read_be(bytes, pos, 4).checked_shl((64 - bits) as u32).unwrap_or(0);
+                pos += 4;
+            }
+        }
+
+        // Each symbol stands for a pair of symbols, down to single values.
+        while pairs.symlen[sym] != 0 {
+            let (left, right) = self.btree(pairs, sym)?;
+            let left_len = *pairs.symlen.get(left)? as i64;
+            let next = if offset < left_len + 1 {
+                left
+            } else {
+                offset -= left_len + 1;
+                right
+            };
+            // only a broken file has a symbol that is not longer than its parts
+            if *pairs.symlen.get(next)? >= pairs.symlen[sym] {
+                return None;
+            }
+            sym = next;
+        }
+        Some(self.btree(pairs, sym)?.0 as u16)
+    }
+
+    fn btree(&self, pairs: &Pairs, sym: usize) -> Option<(usize, usize)> {
+        btree_entry(&self.bytes, pairs.btree, sym)
+    }
+
+    /// DTZ in plies from a stored value.
+    fn map_score(&self, which: usize, value: usize, wdl: Wdl) -> Option<i32> {
+        let pairs = &self.pairs[which];
+        let mut value = value;
+        if pairs.flags & MAPPED != 0 {
+            let map = pairs.map[match wdl {
+                Wdl::Win => 0,
+                Wdl::Loss => 1,
+                Wdl::CursedWin => 2,
+                _ => 3,
+            }];
+            value = if pairs.flags & WIDE != 0 {
+                read_le(&self.bytes, map + 2 + 2 * value, 2)? as usize
+            } else {
+                *self.bytes.get(map + 1 + value)? as usize
+            };
+        }
+        let plies = match wdl {
+            Wdl::Win => pairs.flags & WIN_PLIES != 0,
+            Wdl::Loss => pairs.flags & LOSS_PLIES != 0,
+            _ => false,
+        };
+        let value = if plies { value } else { value * 2 };
+        Some(value as i32 + 1)
+    }
+}
+
+/// The left and right symbols of `sym`, 12 bits each. A single value is the
+/// left one, with 0xfff on the right.
+fn btree_entry(bytes: &[u8], btree: usize, sym: usize) -> Option<(usize, usize)> {
+    let entry = bytes.get(btree + 3 * sym..btree + 3 * sym + 3)?;
+    let left = ((entry[1] as usize & 0xf) << 8) | entry[0] as usize;
+    let right = ((entry[2] as usize) << 4) | (entry[1] as usize >> 4);
+    Some((left, right))
+}
+
+impl Pairs {
+    /// Reads the sizes and the symbols of the table at `pos`, and returns
+    /// where the next table's start.
+    fn set_sizes(&mut self, bytes: &[u8], pos: usize) -> Option<usize> {
+        let mut pos = pos;
+        self.flags = *bytes.get(pos)?;
+        pos += 1;
+        if self.flags & SINGLE_VALUE != 0 {
+            self.min_len = *bytes.get(pos)? as usize;
+            return Some(pos + 1);
+        }
+
+        self.block_size = 1 << *bytes.get(pos)?.min(&30);
+        self.span = 1 << *bytes.get(pos + 1)?.min(&62);
+        self.sparse_entries = self.size().div_ceil(self.span) as usize;
+        let padding = *bytes.get(pos + 2)? as usize;
+        self.blocks = read_le(bytes, pos + 3, 4)? as usize;
+        self.block_lengths = self.blocks + padding;
+        let max_len = *bytes.get(pos + 7)? as usize;
+        self.min_len = *bytes.get(pos + 8)? as usize;
+        pos += 9;
+        if max_len < self.min_len || max_len > 64 || self.min_len == 0 {
+            return None;
+        }
+        self.lowest_sym = pos;
+
+        // Longer codes have lower values. `base[len]` is the lowest code of
+        // length `min_len + len`, padded to 64 bits.
+        let lengths = max_len - self.min_len + 1;
+        self.base = vec![0; lengths];
+        for i in (0..lengths - 1).rev() {
+            let lowest = read_le(bytes, pos + 2 * i, 2)?;
+            let next = read_le(bytes, pos + 2 * i + 2, 2)?;
+            self.base[i] = self.base[i + 1].wrapping_add(lowest).wrapping_sub(next) / 2;
+        }
+        for (i, base) in self.base.iter_mut().enumerate() {
+            *base = base.checked_shl((64 - i - self.min_len) as u32).unwrap_or(0);
+        }
+        pos += 2 * lengths;
+
+        let symbols = read_le(bytes, pos, 2)? as usize;
+        pos += 2;
+        self.btree = pos;
+        self.symlen = vec![0; symbols];
+        let mut visited = vec![false; symbols];
+        for sym in 0..symbols {
+            if !visited[sym] {
+                self.symlen[sym] = self.set_symlen(bytes, sym, &mut visited)?;
+            }
+        }
+        Some(pos + 3 * symbols + (symbols & 1))
+    }
+
+    /// How many values, less one, `sym` stands for.
+    fn set_symlen(&mut self, bytes: &[u8], sym: usize, visited: &mut [bool]) -> Option<u8> {
+        visited[sym] = true;
+        let (left, right) = btree_entry(bytes, self.btree, sym)?;
+        if right == 0xfff {
+            return Some(0);
+        }
+        for child in [left, right] {
+            if !*visited.get(child)? {
+                self.symlen[child] = self.set_symlen(bytes, child, visited)?;
+            }
+        }
+        self.symlen[left].checked_add(self.symlen[right])?.checked_add(1)
+    }
+}
+
+impl std::fmt::Debug for Table {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Table({})", self.name)
+    }
+}
+
+#[cfg(test)]
+mod writer;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::variant::Atomic;
+    use crate::EndgameResult;
+
+    fn tablebase() -> Tablebase {
+        Tablebase::open(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/syzygy")).unwrap()
+    }
+
+    /// Legal positions with `pieces` on squares picked by a simple random
+    /// number generator.
+    fn positions(pieces: &[Piece], count: usize) -> Vec<Game> {
+        let mut games = Vec::new();
+        let mut seed: u64 = 12345;
+        let empty = Game::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        while games.len() < count {
+            let mut game = empty.clone();
+            game.gameboard.iter_mut().for_each(|square| *square = None);
+            for piece in pieces {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                let pos = (seed >> 58) as usize;
+                if piece.piecetype == PieceType::Pawn && !(8..56).contains(&pos) {
+                    continue;
+                }
+                game.gameboard[pos] = Some(piece.clone());
+            }
+            game.turn = if seed & 1 << 20 == 0 { PieceColor::White } else { PieceColor::Black };
+            if game.gameboard.iter().flatten().count() != pieces.len() {
+                continue;
+            }
+            let mut other = game.clone();
+            other.turn = crate::fen::opposite(game.turn);
+            if !other.is_check() {
+                games.push(Game::from_fen(&game.to_fen()).unwrap());
+            }
+        }
+        games
+    }
+
+    #[test]
+    fn same_as_endgame_tables() {
+        let tablebase = tablebase();
+        for (strong, weak) in [(PieceColor::White, PieceColor::Black), (PieceColor::Black, PieceColor::White)] {
+            for piecetype in [PieceType::Queen, PieceType::Rook, PieceType::Pawn] {
+                let pieces = [Piece::new(PieceType::King, strong), Piece::new(piecetype, strong), Piece::new(PieceType::King, weak)];
+                for game in positions(&pieces, 60) {
+                    let fen = game.to_fen();
+                    let wdl = tablebase.probe_wdl(&game);
+                    let dtz = tablebase.probe_dtz(&game).unwrap();
+                    match game.endgame_result().unwrap() {
+                        EndgameResult::Draw => {
+                            assert_eq!(wdl, Some(Wdl::Draw), "{}", fen);
+                            assert_eq!(dtz, 0, "{}", fen);
+                        }
+                        EndgameResult::Win(moves) => {
+                            assert_eq!(wdl, Some(Wdl::Win), "{}", fen);
+                            if piecetype == PieceType::Pawn {
+                                assert!(dtz > 0, "{}", fen);
+                            } else {
+                                assert_eq!(dtz, 2 * moves as i32 - 1, "{}", fen);
+                            }
+                        }
+                        EndgameResult::Loss(moves) => {
+                            assert_eq!(wdl, Some(Wdl::Loss), "{}", fen);
+                            if piecetype == PieceType::Pawn {
+                                assert!(dtz < 0, "{}", fen);
+                            } else {
+                                assert_eq!(dtz, -2 * moves.max(1) as i32 + (moves == 0) as i32, "{}", fen);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        let game = Game::from_fen("8/8/8/8/8/2k5/8/K1N5 b - - 0 1").unwrap();
+        assert_eq!(tablebase.probe_wdl(&game), Some(Wdl::Draw));
+        assert_eq!(tablebase.probe_dtz(&game), Some(0));
+    }
+
+    #[test]
+    fn four_pieces() {
+        let tablebase = tablebase();
+        assert_eq!(tablebase.max_pieces(), 4);
+
+        // The longest win with a queen against a rook takes 31 moves to
+        // the capture. Every move of the best line brings it one ply closer.
+        let mut game = Game::from_fen("8/8/8/8/Q7/5k2/8/K3r3 w - - 0 1").unwrap();
+        assert_eq!(tablebase.probe_wdl(&game), Some(Wdl::Win));
+        let mut dtz = tablebase.probe_dtz(&game).unwrap();
+        assert_eq!(dtz, 61);
+        while dtz.abs() > 1 {
+            assert!(game.replay(&tablebase.best_move(&game).unwrap()));
+            let next = tablebase.probe_dtz(&game).unwrap();
+            assert_eq!(next, -(dtz - dtz.signum()), "{}", game.to_fen());
+            dtz = next;
+        }
+        assert!(game.replay(&tablebase.best_move(&game).unwrap()));
+        assert_eq!(game.gameboard.iter().flatten().count(), 3);
+        assert_eq!(tablebase.probe_wdl(&game), Some(Wdl::Loss));
+
+        // the rook checks along the file and takes the queen behind the king
+        let game = Game::from_fen("4Q3/7k/8/8/4K3/8/8/7r b - - 0 1").unwrap();
+        assert_eq!(tablebase.probe_wdl(&game), Some(Wdl::Win));
+        assert_eq!(tablebase.probe_dtz(&game), Some(3));
+        assert_eq!(tablebase.best_move(&game).unwrap().to_string(), "h1e1");
+
+        // mates found by searching are quickest wins in the table
+        let pieces = [
+            Piece::new(PieceType::King, PieceColor::White),
+            Piece::new(PieceType::Queen, PieceColor::White),
+            Piece::new(PieceType::King, PieceColor::Black),
+            Piece::new(PieceType::Rook, PieceColor::Black),
+        ];
+        let mut mates = 0;
+        for game in positions(&pieces, 400) {
+            if game.turn == PieceColor::White && game.find_mate(1).is_some() {
+                assert_eq!(tablebase.probe_wdl(&game), Some(Wdl::Win), "{}", game.to_fen());
+                assert_eq!(tablebase.probe_dtz(&game), Some(1), "{}", game.to_fen());
+                mates += 1;
+            }
+        }
+        assert!(mates > 0);
+    }
+
+    #[test]
+    fn best_move_wins() {
+        let tablebase = tablebase();
+        for fen in ["8/8/8/4k3/8/8/8/KR6 w - - 0 1", "4k3/8/4K3/4P3/8/8/8/8 w - - 0 1", "4k3/8/8/8/8/8/8/q3K3 b - - 0 1"] {
+            let mut game = Game::from_fen(fen).unwrap();
+            for _ in 0..100 {
+                if game.is_checkmate() {
+                    break;
+                }
+                let _move = tablebase.best_move(&game).unwrap();
+                assert!(game.replay(&_move), "{} {}", game.to_fen(), _move);
+            }
+            assert!(game.is_checkmate(), "{}", fen);
+        }
+
+        // the move that mates, not just any winning move
+        let game = Game::from_fen("k7/8/1K6/8/8/8/7Q/8 w - - 0 1").unwrap();
+        assert_eq!(tablebase.probe_dtz(&game), Some(1));
+        assert_eq!(tablebase.best_move(&game).unwrap().to_string(), "h2h8");
+    }
+
+    #[test]
+    fn unusable_positions() {
+        let tablebase = tablebase();
+        let cases = [
+            Game::from_fen("8/8/8/4k3/8/8/8/KRN5 w - - 0 1").unwrap(),
+            Game::from_fen("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1").unwrap(),
+            Game::from_fen_variant("8/8/8/4k3/8/8/8/KR6 w - - 0 1", Atomic).unwrap(),
+        ];
+        for game in cases {
+            assert_eq!(tablebase.probe_wdl(&game), None);
+            assert_eq!(tablebase.probe_dtz(&game), None);
+            assert_eq!(tablebase.best_move(&game), None);
+        }
+        assert!(Tablebase::open("/no/such/directory").is_none());
+    }
+
+    #[test]
+    fn broken_files() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/syzygy");
+        let bytes = fs::read(format!("{}/KRvK.rtbz", dir)).unwrap();
+        assert!(Table::parse(bytes[..bytes.len() / 2].to_vec(), "KRvK", true).is_none());
+        assert!(Table::parse(bytes.clone(), "KRvK", false).is_none());
+        assert!(Table::parse(bytes.clone(), "KRRRRRRvK", true).is_none());
+
+        // garbage in the right places gives wrong values, but no panic
+        let mut seed: u64 = 1;
+        for _ in 0..50 {
+            let mut broken = bytes.clone();
+            for _ in 0..20 {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                let pos = (seed >> 33) as usize % broken.len();
+                broken[pos] = (seed >> 8) as u8;
+            }
+            if let Some(table) = Table::parse(broken, "KRvK", true) {
+                for index in (0..table.pairs[0].size()).step_by(97) {
+                    table.decompress(0, index);
+                }
+            }
+        }
+    }
+}
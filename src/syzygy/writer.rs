@@ -0,0 +1,650 @@
+//! Writes the small tables in tests/syzygy by retrograde analysis, in the
+//! same format as the real Syzygy files. Only for tables with at most one
+//! piece of each kind, pawns on one side and at most three pieces with a
+//! pawn, which is all the tests need. Run with
+//! `cargo test --release --features syzygy -- --ignored`.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fs;
+
+use super::*;
+
+const BLOCK_SIZE_LOG: u8 = 8;
+const SPAN_LOG: u8 = 10;
+/// Keeps the offsets in the sparse index below 65536.
+const MAX_BLOCK_VALUES: usize = 60000;
+
+/// The steps of the pieces, as (rank, file). Queens step like kings.
+const KING_STEPS: [(i32, i32); 8] = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)];
+const ROOK_STEPS: [(i32, i32); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+const BISHOP_STEPS: [(i32, i32); 4] = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
+const KNIGHT_STEPS: [(i32, i32); 8] = [(-2, -1), (-2, 1), (-1, -2), (-1, 2), (1, -2), (1, 2), (2, -1), (2, 1)];
+
+/// Where a move leads: a position of the same table, or the result for the
+/// side to move in a smaller one.
+#[derive(Copy, Clone)]
+enum Next {
+    Same(usize),
+    Done(i8),
+}
+
+/// The results of a table, by `key` and side to move: the WDL value from
+/// -2 to 2, and the DTZ in plies from 0 for positions that are won or lost.
+struct Solved {
+    pieces: Vec<Piece>,
+    wdl: Vec<i8>,
+    dtz: Vec<u16>,
+    legal: Vec<bool>,
+}
+
+/// The pieces of a table, white first, in the order of its name.
+fn table_pieces(name: &str) -> Vec<Piece> {
+    let (white, black) = name.split_once('v').unwrap();
+    let piece = |letter, color| {
+        let (piecetype, _) = PIECE_LETTERS.into_iter().find(|(_, l)| *l == letter).unwrap();
+        Piece::new(piecetype, color)
+    };
+    let mut pieces: Vec<Piece> = white.chars().map(|letter| piece(letter, PieceColor::White)).collect();
+    pieces.extend(black.chars().map(|letter| piece(letter, PieceColor::Black)));
+    pieces
+}
+
+/// Positions are numbered by the squares of the pieces, 6 bits each, and
+/// the side to move.
+fn key(pieces: &[Piece], board: &[Option<Piece>], turn: PieceColor) -> usize {
+    let mut key = 0;
+    for (i, piece) in pieces.iter().enumerate() {
+        let pos = board
+            .iter()
+            .position(|p| matches!(p, Some(p) if p.piecetype == piece.piecetype && p.piececolor == piece.piececolor))
+            .unwrap();
+        key |= pos << (6 * i);
+    }
+    key * 2 + (turn == PieceColor::Black) as usize
+}
+
+/// Puts the position with `key` on the board of `game`. False if it is not
+/// legal.
+fn set_up(game: &mut Game, pieces: &[Piece], key: usize) -> bool {
+    game.gameboard.iter_mut().for_each(|square| *square = None);
+    for (i, piece) in pieces.iter().enumerate() {
+        let pos = (key >> 1 >> (6 * i)) & 63;
+        let last_rank = pos / 8 == 0 || pos / 8 == 7;
+        if game.gameboard[pos].is_some() || (piece.piecetype == PieceType::Pawn && last_rank) {
+            return false;
+        }
+        let mut piece = piece.clone();
+        piece.hasmoved = match piece.piececolor {
+            PieceColor::White => pos / 8 != 6,
+            PieceColor::Black => pos / 8 != 1,
+        };
+        game.gameboard[pos] = Some(piece);
+    }
+    game.turn = if key & 1 == 0 { PieceColor::Black } else { PieceColor::White };
+    let legal = !game.is_check();
+    game.turn = crate::fen::opposite(game.turn);
+    legal
+}
+
+/// Solves a table, with the tables it can turn into already in `solved`.
+fn solve(name: &str, solved: &HashMap<String, Solved>) -> Solved {
+    let pieces = table_pieces(name);
+    assert!(pieces.len() <= 3, "{} has too many pieces", name);
+    let size = 2 << (6 * pieces.len());
+    let mut game = Game::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let mut legal = vec![false; size];
+    let mut next: Vec<Vec<(Next, bool)>> = vec![Vec::new(); size];
+    let mut moves = MoveList::new();
+    for key in 0..size {
+        if !set_up(&mut game, &pieces, key) {
+            continue;
+        }
+        legal[key] = true;
+        moves.clear();
+        game.generate_moves(&mut moves);
+        for _move in &moves {
+            let pawn = matches!(&game.gameboard[_move.from], Some(piece) if piece.piecetype == PieceType::Pawn);
+            let zeroing = pawn || is_capture(&game, _move);
+            let undo = game.make_move_unchecked(_move);
+            let white = material_name(&game.gameboard, PieceColor::White);
+            let black = material_name(&game.gameboard, PieceColor::Black);
+            let after = format!("{}v{}", white, black);
+            let to = if after == name {
+                Next::Same(self::key(&pieces, &game.gameboard, game.turn))
+            } else if after == "KvK" {
+                Next::Done(0)
+            } else {
+                let table = solved.get(&after).unwrap_or_else(|| panic!("solve {} first", after));
+                Next::Done(table.wdl[self::key(&table.pieces, &game.gameboard, game.turn)])
+            };
+            game.unmake_move(_move, undo);
+            next[key].push((to, zeroing));
+        }
+    }
+
+    // Wins and losses, ignoring the 50 move rule.
+    let mut wdl: Vec<Option<i8>> = vec![None; size];
+    for key in 0..size {
+        if legal[key] && next[key].is_empty() {
+            set_up(&mut game, &pieces, key);
+            wdl[key] = Some(if game.is_check() { -2 } else { 0 });
+        }
+    }
+    let value = |wdl: &[Option<i8>], to: Next| match to {
+        Next::Same(key) => wdl[key],
+        Next::Done(value) => Some(value),
+    };
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for key in 0..size {
+            if !legal[key] || wdl[key].is_some() {
+                continue;
+            }
+            if next[key].iter().any(|(to, _)| value(&wdl, *to) == Some(-2)) {
+                wdl[key] = Some(2);
+                changed = true;
+            } else if next[key].iter().all(|(to, _)| value(&wdl, *to) == Some(2)) {
+                wdl[key] = Some(-2);
+                changed = true;
+            }
+        }
+    }
+    let wdl: Vec<i8> = wdl.into_iter().map(|value| value.unwrap_or(0)).collect();
+
+    // Plies to the next capture or pawn move, one layer at a time. A won
+    // position takes the quickest move to a lost one, a lost position the
+    // slowest move.
+    let mut dtz: Vec<Option<u16>> = vec![None; size];
+    for key in 0..size {
+        if legal[key] && next[key].is_empty() && wdl[key] == -2 {
+            dtz[key] = Some(0);
+        }
+    }
+    let mut unsolved = (0..size).filter(|key| legal[*key] && wdl[*key] != 0 && dtz[*key].is_none()).count();
+    let mut plies = 1;
+    while unsolved > 0 {
+        assert!(plies <= 100, "{} has cursed wins", name);
+        for key in 0..size {
+            if !legal[key] || wdl[key] == 0 || dtz[key].is_some() {
+                continue;
+            }
+            let costs = next[key].iter().map(|(to, zeroing)| {
+                let after = match to {
+                    Next::Same(key) => wdl[*key],
+                    Next::Done(value) => *value,
+                };
+                (after, if *zeroing { Some(1) } else { to_dtz(&dtz, *to).map(|dtz| dtz + 1) })
+            });
+            let best = if wdl[key] == 2 {
+                costs.filter(|(after, _)| *after == -2).filter_map(|(_, cost)| cost).min()
+            } else {
+                costs.map(|(_, cost)| cost).collect::<Option<Vec<u16>>>().and_then(|costs| costs.into_iter().max())
+            };
+            if best == Some(plies) {
+                dtz[key] = Some(plies);
+                unsolved -= 1;
+            }
+        }
+        plies += 1;
+    }
+
+    Solved {
+        pieces,
+        wdl,
+        dtz: dtz.into_iter().map(|dtz| dtz.unwrap_or(0)).collect(),
+        legal,
+    }
+}
+
+/// Solves a table without pawns by working back from the mates and the
+/// captures, one ply at a time. Unlike `solve` it does not keep the moves
+/// of every position, so it can do tables with four pieces.
+fn solve_backwards(name: &str, solved: &HashMap<String, Solved>) -> Solved {
+    let pieces = table_pieces(name);
+    assert!(pieces.iter().all(|piece| piece.piecetype != PieceType::Pawn), "{} has pawns", name);
+    let size = 2 << (6 * pieces.len());
+    let mut game = Game::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let mut legal = vec![false; size];
+    let mut wdl = vec![0i8; size];
+    let mut dtz = vec![0u16; size];
+    // moves that are not captures and whose result is not known yet
+    let mut unknown = vec![0u8; size];
+    // positions with a capture that does not lose, so they are not lost
+    let mut saved = vec![false; size];
+    let mut layers: Vec<Vec<usize>> = vec![Vec::new(), Vec::new()];
+    let mut moves = MoveList::new();
+    for key in 0..size {
+        if !set_up(&mut game, &pieces, key) {
+            continue;
+        }
+        legal[key] = true;
+        moves.clear();
+        game.generate_moves(&mut moves);
+        if moves.is_empty() {
+            if game.is_check() {
+                wdl[key] = -2;
+                layers[0].push(key);
+            }
+            continue;
+        }
+        let mut wins = false;
+        for _move in &moves {
+            if game.gameboard[_move.to].is_none() {
+                unknown[key] += 1;
+                continue;
+            }
+            let undo = game.make_move_unchecked(_move);
+            let value = smaller_table_value(&game, solved);
+            game.unmake_move(_move, undo);
+            wins |= value == -2;
+            saved[key] |= value != 2;
+        }
+        if wins || (unknown[key] == 0 && !saved[key]) {
+            wdl[key] = if wins { 2 } else { -2 };
+            dtz[key] = 1;
+            layers[1].push(key);
+        }
+    }
+
+    // A position before a lost one is won a ply later, and a position is
+    // lost once every move leads to a won one.
+    let mut plies = 0;
+    while plies < layers.len() {
+        let layer = std::mem::take(&mut layers[plies]);
+        for key in layer {
+            for before in moves_before(&pieces, key) {
+                if !legal[before] || wdl[before] != 0 {
+                    continue;
+                }
+                if wdl[key] == -2 {
+                    wdl[before] = 2;
+                } else {
+                    unknown[before] -= 1;
+                    if unknown[before] > 0 || saved[before] {
+                        continue;
+                    }
+                    wdl[before] = -2;
+                }
+                assert!(plies < 100, "{} has cursed wins", name);
+                dtz[before] = plies as u16 + 1;
+                if layers.len() == plies + 1 {
+                    layers.push(Vec::new());
+                }
+                layers[plies + 1].push(before);
+            }
+        }
+        plies += 1;
+    }
+
+    Solved { pieces, wdl, dtz, legal }
+}
+
+/// The WDL value for the side to move after a capture, from the table of
+/// the pieces left.
+fn smaller_table_value(game: &Game, solved: &HashMap<String, Solved>) -> i8 {
+    let white = material_name(&game.gameboard, PieceColor::White);
+    let black = material_name(&game.gameboard, PieceColor::Black);
+    if white == "K" && black == "K" {
+        return 0;
+    }
+    if let Some(table) = solved.get(&format!("{}v{}", white, black)) {
+        return table.wdl[key(&table.pieces, &game.gameboard, game.turn)];
+    }
+
+    // the same position with the colors the other way round
+    let mut board = vec![None; 64];
+    for (pos, square) in game.gameboard.iter().enumerate() {
+        board[pos ^ 56] = square.clone().map(|mut piece| {
+            piece.piececolor = crate::fen::opposite(piece.piececolor);
+            piece
+        });
+    }
+    let name = format!("{}v{}", black, white);
+    let table = solved.get(&name).unwrap_or_else(|| panic!("solve {} first", name));
+    table.wdl[key(&table.pieces, &board, crate::fen::opposite(game.turn))]
+}
+
+/// The positions, legal or not, from which the side not to move reached
+/// the position with `key` by a move that is not a capture.
+fn moves_before(pieces: &[Piece], key: usize) -> Vec<usize> {
+    let squares: Vec<usize> = (0..pieces.len()).map(|i| (key >> 1 >> (6 * i)) & 63).collect();
+    let moved = if key & 1 == 0 { PieceColor::Black } else { PieceColor::White };
+    let mut before = Vec::new();
+    for (i, piece) in pieces.iter().enumerate() {
+        if piece.piececolor != moved {
+            continue;
+        }
+        let (steps, slides): (&[(i32, i32)], bool) = match piece.piecetype {
+            PieceType::King => (&KING_STEPS, false),
+            PieceType::Queen => (&KING_STEPS, true),
+            PieceType::Rook => (&ROOK_STEPS, true),
+            PieceType::Bishop => (&BISHOP_STEPS, true),
+            PieceType::Knight => (&KNIGHT_STEPS, false),
+            PieceType::Pawn => panic!("pawns can not move back"),
+        };
+        for (rank_step, file_step) in steps {
+            let (mut rank, mut file) = ((squares[i] / 8) as i32, (squares[i] % 8) as i32);
+            loop {
+                rank += rank_step;
+                file += file_step;
+                if !(0..8).contains(&rank) || !(0..8).contains(&file) {
+                    break;
+                }
+                let pos = (rank * 8 + file) as usize;
+                if squares.contains(&pos) {
+                    break;
+                }
+                let shift = 6 * i + 1;
+                before.push((key & !(63 << shift) | pos << shift) ^ 1);
+                if !slides {
+                    break;
+                }
+            }
+        }
+    }
+    before
+}
+
+fn to_dtz(dtz: &[Option<u16>], to: Next) -> Option<u16> {
+    match to {
+        Next::Same(key) => dtz[key],
+        Next::Done(_) => None,
+    }
+}
+
+/// The bytes of the WDL or DTZ file of a solved table.
+fn write_table(name: &str, solved: &Solved, dtz: bool) -> Vec<u8> {
+    let material = Material::parse(name).unwrap();
+    assert!(material.pawns[1] == 0, "{} has pawns on both sides", name);
+    let mut table = Table::new(name, material.clone(), dtz);
+    let sides = table.pairs.len() / table.files;
+
+    // The leading pawns first, as the index needs.
+    let mut codes: Vec<u8> = solved.pieces.iter().map(piece_code).collect();
+    codes.sort_by_key(|code| *code != 1);
+    for file in 0..table.files {
+        for side in 0..sides {
+            let pairs = &mut table.pairs[file * sides + side];
+            pairs.pieces[..codes.len()].copy_from_slice(&codes);
+            pairs.set_groups(&material, [0, 0xf], file);
+        }
+    }
+
+    let mut values: Vec<Vec<Option<u16>>> = table.pairs.iter().map(|pairs| vec![None; pairs.size() as usize]).collect();
+    let mut game = Game::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    for key in (0..solved.legal.len()).filter(|key| solved.legal[*key]) {
+        set_up(&mut game, &solved.pieces, key);
+        if dtz && game.turn == PieceColor::Black {
+            continue;
+        }
+        let (which, _, index) = table.index(&game.gameboard, game.turn, false).unwrap();
+        let value = if dtz {
+            if solved.wdl[key] == 0 {
+                continue;
+            }
+            solved.dtz[key].max(1) - 1
+        } else {
+            (solved.wdl[key] + 2) as u16
+        };
+        let stored = &mut values[which][index as usize];
+        assert!(stored.is_none_or(|stored| stored == value), "{} index {} differs", name, index);
+        *stored = Some(value);
+    }
+
+    let flags = if dtz { WIN_PLIES | LOSS_PLIES } else { 0 };
+    let compressed: Vec<Compressed> = values
+        .iter()
+        .map(|values| {
+            // Positions that can not happen take the value before them.
+            let mut last = values.iter().flatten().next().copied().unwrap_or(0);
+            let values: Vec<u16> = values
+                .iter()
+                .map(|value| {
+                    last = value.unwrap_or(last);
+                    last
+                })
+                .collect();
+            compress(&values, flags)
+        })
+        .collect();
+
+    let mut bytes = if dtz { DTZ_MAGIC.to_vec() } else { WDL_MAGIC.to_vec() };
+    bytes.push((sides == 2) as u8 | (material.has_pawns as u8) << 1);
+    for _ in 0..table.files {
+        bytes.push(0);
+        bytes.extend(codes.iter().map(|code| code << 4 | code));
+    }
+    align(&mut bytes, 2);
+    for part in &compressed {
+        bytes.extend(&part.sizes);
+    }
+    if dtz {
+        align(&mut bytes, 2);
+    }
+    for part in &compressed {
+        bytes.extend(&part.sparse_index);
+    }
+    for part in &compressed {
+        bytes.extend(&part.block_lengths);
+    }
+    for part in &compressed {
+        align(&mut bytes, 64);
+        bytes.extend(&part.blocks);
+    }
+    bytes
+}
+
+fn align(bytes: &mut Vec<u8>, to: usize) {
+    bytes.resize(bytes.len().div_ceil(to) * to, 0);
+}
+
+/// The values of one table in the parts `Table::parse` reads.
+struct Compressed {
+    sizes: Vec<u8>,
+    sparse_index: Vec<u8>,
+    block_lengths: Vec<u8>,
+    blocks: Vec<u8>,
+}
+
+fn compress(values: &[u16], flags: u8) -> Compressed {
+    if values.iter().all(|value| *value == values[0]) {
+        return Compressed {
+            sizes: vec![flags | SINGLE_VALUE, values[0] as u8],
+            sparse_index: Vec::new(),
+            block_lengths: Vec::new(),
+            blocks: Vec::new(),
+        };
+    }
+
+    // Re-Pair: replace the most common pair of symbols with a new symbol
+    // until pairs are rare.
+    let mut leaves: Vec<u16> = values.to_vec();
+    leaves.sort();
+    leaves.dedup();
+    let mut btree: Vec<(usize, usize)> = leaves.iter().map(|value| (*value as usize, 0xfff)).collect();
+    let mut symlen: Vec<usize> = vec![0; leaves.len()];
+    let mut symbols: Vec<usize> = values.iter().map(|value| leaves.binary_search(value).unwrap()).collect();
+    while btree.len() < 4000 {
+        let mut counts: HashMap<(usize, usize), usize> = HashMap::new();
+        for pair in symbols.windows(2) {
+            if symlen[pair[0]] + symlen[pair[1]] < 255 {
+                *counts.entry((pair[0], pair[1])).or_default() += 1;
+            }
+        }
+        let Some((pair, count)) = counts.into_iter().max_by_key(|(pair, count)| (*count, Reverse(*pair))) else {
+            break;
+        };
+        // big tables stop sooner, which keeps this fast enough
+        if count < 8.max(values.len() / 4096) {
+            break;
+        }
+        let sym = btree.len();
+        btree.push(pair);
+        symlen.push(symlen[pair.0] + symlen[pair.1] + 1);
+        let mut replaced = Vec::with_capacity(symbols.len());
+        let mut i = 0;
+        while i < symbols.len() {
+            if i + 1 < symbols.len() && (symbols[i], symbols[i + 1]) == pair {
+                replaced.push(sym);
+                i += 2;
+            } else {
+                replaced.push(symbols[i]);
+                i += 1;
+            }
+        }
+        symbols = replaced;
+    }
+
+    // Huffman code lengths, then new numbers for the symbols so that the
+    // longest codes come first, as the canonical code needs.
+    let mut counts = vec![0; btree.len()];
+    for sym in &symbols {
+        counts[*sym] += 1;
+    }
+    let lengths = huffman_lengths(&counts);
+    let mut order: Vec<usize> = (0..btree.len()).collect();
+    order.sort_by_key(|sym| (lengths[*sym] == 0, Reverse(lengths[*sym]), *sym));
+    let mut renumber = vec![0; btree.len()];
+    for (new, old) in order.iter().enumerate() {
+        renumber[*old] = new;
+    }
+    let max_len = *lengths.iter().max().unwrap();
+    let min_len = *lengths.iter().filter(|len| **len > 0).min().unwrap();
+    assert!(max_len <= 32);
+    let lens = max_len - min_len + 1;
+    let mut lowest_sym = vec![0; lens];
+    let mut count_of_len = vec![0; lens];
+    for (new, old) in order.iter().enumerate().rev() {
+        if lengths[*old] > 0 {
+            lowest_sym[lengths[*old] - min_len] = new;
+            count_of_len[lengths[*old] - min_len] += 1;
+        }
+    }
+    for i in (0..lens - 1).rev() {
+        if count_of_len[i] == 0 {
+            lowest_sym[i] = lowest_sym[i + 1] + count_of_len[i + 1];
+        }
+    }
+    let mut base = vec![0u64; lens];
+    for i in (0..lens - 1).rev() {
+        assert!((base[i + 1] + count_of_len[i + 1] as u64).is_multiple_of(2));
+        base[i] = (base[i + 1] + count_of_len[i + 1] as u64) / 2;
+    }
+    let code = |sym: usize| -> (u64, usize) {
+        let len = lengths[sym];
+        let new = renumber[sym];
+        (base[len - min_len] + (new - lowest_sym[len - min_len]) as u64, len)
+    };
+
+    // Fill the blocks with whole symbols.
+    let block_bits = 8 << BLOCK_SIZE_LOG;
+    let mut blocks: Vec<u8> = Vec::new();
+    let mut block_values: Vec<usize> = Vec::new();
+    let mut bits: Vec<bool> = Vec::new();
+    let mut count = 0;
+    let flush = |bits: &mut Vec<bool>, count: &mut usize, blocks: &mut Vec<u8>, block_values: &mut Vec<usize>| {
+        let mut block = vec![0u8; 1 << BLOCK_SIZE_LOG];
+        for (i, bit) in bits.iter().enumerate() {
+            block[i / 8] |= (*bit as u8) << (7 - i % 8);
+        }
+        blocks.extend(block);
+        block_values.push(*count);
+        bits.clear();
+        *count = 0;
+    };
+    for sym in &symbols {
+        let (code, len) = code(*sym);
+        if bits.len() + len > block_bits || count + symlen[*sym] + 1 > MAX_BLOCK_VALUES {
+            flush(&mut bits, &mut count, &mut blocks, &mut block_values);
+        }
+        bits.extend((0..len).rev().map(|bit| code >> bit & 1 == 1));
+        count += symlen[*sym] + 1;
+    }
+    flush(&mut bits, &mut count, &mut blocks, &mut block_values);
+
+    let mut sizes = vec![flags, BLOCK_SIZE_LOG, SPAN_LOG, 0];
+    sizes.extend((block_values.len() as u32).to_le_bytes());
+    sizes.push(max_len as u8);
+    sizes.push(min_len as u8);
+    for lowest in &lowest_sym {
+        sizes.extend((*lowest as u16).to_le_bytes());
+    }
+    sizes.extend((btree.len() as u16).to_le_bytes());
+    for old in &order {
+        let (left, right) = btree[*old];
+        let (left, right) = if right == 0xfff { (left, right) } else { (renumber[left], renumber[right]) };
+        sizes.extend([left as u8, (left >> 8) as u8 | (right << 4) as u8, (right >> 4) as u8]);
+    }
+    if btree.len() % 2 == 1 {
+        sizes.push(0);
+    }
+
+    // Where the value in the middle of every span is.
+    let span = 1 << SPAN_LOG;
+    let mut starts = vec![0];
+    for count in &block_values {
+        starts.push(starts.last().unwrap() + count);
+    }
+    let mut sparse_index = Vec::new();
+    for k in 0..values.len().div_ceil(span) {
+        let middle = k * span + span / 2;
+        let block = starts[..block_values.len()].iter().rposition(|start| *start <= middle).unwrap();
+        sparse_index.extend((block as u32).to_le_bytes());
+        sparse_index.extend(u16::try_from(middle - starts[block]).unwrap().to_le_bytes());
+    }
+    let block_lengths = block_values.iter().flat_map(|count| (*count as u16 - 1).to_le_bytes()).collect();
+
+    Compressed { sizes, sparse_index, block_lengths, blocks }
+}
+
+/// The Huffman code length of each symbol, 0 for unused ones.
+fn huffman_lengths(counts: &[usize]) -> Vec<usize> {
+    let mut heap: BinaryHeap<Reverse<(usize, usize)>> = BinaryHeap::new();
+    let mut parent: Vec<usize> = vec![usize::MAX; counts.len()];
+    for (sym, count) in counts.iter().enumerate() {
+        if *count > 0 {
+            heap.push(Reverse((*count, sym)));
+        }
+    }
+    if heap.len() == 1 {
+        return counts.iter().map(|count| (*count > 0) as usize).collect();
+    }
+    while heap.len() > 1 {
+        let Reverse((first, a)) = heap.pop().unwrap();
+        let Reverse((second, b)) = heap.pop().unwrap();
+        let node = parent.len();
+        parent.push(usize::MAX);
+        parent[a] = node;
+        parent[b] = node;
+        heap.push(Reverse((first + second, node)));
+    }
+    (0..counts.len())
+        .map(|sym| {
+            let mut len = 0;
+            let mut node = sym;
+            while counts[sym] > 0 && parent[node] != usize::MAX {
+                node = parent[node];
+                len += 1;
+            }
+            len
+        })
+        .collect()
+}
+
+#[test]
+#[ignore]
+fn write_fixtures() {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/syzygy");
+    let mut solved = HashMap::new();
+    for name in ["KQvK", "KRvK", "KBvK", "KNvK", "KPvK"] {
+        let table = solve(name, &solved);
+        fs::write(format!("{}/{}.rtbw", dir, name), write_table(name, &table, false)).unwrap();
+        fs::write(format!("{}/{}.rtbz", dir, name), write_table(name, &table, true)).unwrap();
+        solved.insert(name.to_string(), table);
+    }
+    let table = solve_backwards("KQvKR", &solved);
+    fs::write(format!("{}/KQvKR.rtbw", dir), write_table("KQvKR", &table, false)).unwrap();
+    fs::write(format!("{}/KQvKR.rtbz", dir), write_table("KQvKR", &table, true)).unwrap();
+}
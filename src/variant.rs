@@ -0,0 +1,665 @@
+//! Rules that differ between chess variants.
+//!
+//! A `Game` asks its `Variant` which moves are allowed, what happens after a
+//! move and when the game is over. The standard rules are the default for
+//! every method, so a house variant only overrides what it changes:
+//!
+//! ```
+//! use erikrub_chess_lib::variant::Variant;
+//! use erikrub_chess_lib::{Game, Move, PieceType};
+//!
+//! /// Knights may not capture.
+//! struct PeacefulKnights;
+//!
+//! impl Variant for PeacefulKnights {
+//!     fn name(&self) -> &str {
+//!         "Peaceful knights"
+//!     }
+//!
+//!     fn is_legal(&self, game: &Game, _move: &Move) -> bool {
+//!         let knight = matches!(&game.gameboard[_move.from], Some(piece) if piece.piecetype == PieceType::Knight);
+//!         !(knight && game.gameboard[_move.to].is_some()) && !game.leaves_king_in_check(_move)
+//!     }
+//! }
+//!
+//! let game = Game::new_variant(PeacefulKnights);
+//! assert_eq!(game.get_legal_moves().len(), 20);
+//! ```
+
+use crate::fen::{find_king, opposite};
+use crate::{Game, Move, Outcome, Piece, PieceColor, PieceType};
+
+pub trait Variant: Send + Sync {
+    /// Name used in the PGN "Variant" tag, e.g. "Standard".
+    fn name(&self) -> &str;
+
+    /// The position a new game of this variant starts from, as FEN.
+    fn start_fen(&self) -> &str {
+        crate::fen::START_FEN
+    }
+
+    /// Tells if a move the pieces can make is allowed. Called for every
+    /// possible move of the side to move. By default a move may not leave
+    /// the own king attacked.
+    fn is_legal(&self, game: &Game, _move: &Move) -> bool {
+        !game.leaves_king_in_check(_move)
+    }
+
+    /// Tells if the king of `color` is attacked on `board`, which is the
+    /// current board or the board after a possible move. Always false for a
+    /// side without a king.
+    fn king_attacked(&self, game: &Game, board: &[Option<Piece>], color: PieceColor) -> bool {
+        match find_king(board, color) {
+            Some(king) => game.square_attacked(board, king, opposite(color)),
+            None => false,
+        }
+    }
+
+    /// Removes moves that are only allowed in some positions, e.g. when
+    /// capturing is compulsory. `moves` are the moves `is_legal` allowed.
+    fn filter_moves(&self, _game: &Game, _moves: &mut Vec<Move>) {}
+
+    /// Called when a piece has been moved, before the promotion is chosen and
+    /// the turn passes. `captured` is the piece that stood on `_move.to`.
+    fn after_move(&self, _game: &mut Game, _move: &Move, _captured: Option<Piece>) {}
+
+    /// The result if the game is over in the current position. Called after
+    /// every move, when the legal moves of the side to move are known. By
+    /// default checkmate wins and stalemate is a draw.
+    fn outcome(&self, game: &Game) -> Option<Outcome> {
+        if !game.get_legal_moves().is_empty() {
+            return None;
+        }
+        if game.is_check() {
+            Some(Outcome::win(opposite(game.turn), "checkmate"))
+        } else {
+            Some(Outcome::draw("stalemate"))
+        }
+    }
+
+    /// Pieces a pawn may promote to.
+    fn promotion_pieces(&self) -> &[PieceType] {
+        &[PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight]
+    }
+
+    /// Number of checks that wins the game, if checks are counted. The checks
+    /// each side has left are then written in FEN, e.g. "3+3".
+    fn check_limit(&self) -> Option<u32> {
+        None
+    }
+
+    /// Tells if each side must have exactly one king.
+    fn kings_required(&self) -> bool {
+        true
+    }
+
+    /// Tells if pawns may stand on their own first rank. They can then move
+    /// two squares from there, like from the second rank.
+    fn pawns_on_first_rank(&self) -> bool {
+        false
+    }
+
+    /// Tells if captured pieces can be dropped back on the board. The
+    /// pockets are then written in FEN, e.g. "[Qn]".
+    fn uses_pockets(&self) -> bool {
+        false
+    }
+}
+
+/// Standard chess.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Standard;
+
+impl Variant for Standard {
+    fn name(&self) -> &str {
+        "Standard"
+    }
+}
+
+/// Bringing the king to one of the four center squares wins.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct KingOfTheHill;
+
+impl Variant for KingOfTheHill {
+    fn name(&self) -> &str {
+        "King of the Hill"
+    }
+
+    fn outcome(&self, game: &Game) -> Option<Outcome> {
+        let mover = opposite(game.turn);
+        // d5, e5, d4, e4
+        if let Some(king) = find_king(&game.gameboard, mover) {
+            if [27, 28, 35, 36].contains(&king) {
+                return Some(Outcome::win(mover, "king of the hill"));
+            }
+        }
+        Standard.outcome(game)
+    }
+}
+
+/// Giving check for the third time wins.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ThreeCheck;
+
+impl Variant for ThreeCheck {
+    fn name(&self) -> &str {
+        "Three-check"
+    }
+
+    fn outcome(&self, game: &Game) -> Option<Outcome> {
+        let mover = opposite(game.turn);
+        if game.get_checks(mover) >= 3 {
+            return Some(Outcome::win(mover, "three checks"));
+        }
+        Standard.outcome(game)
+    }
+
+    fn check_limit(&self) -> Option<u32> {
+        Some(3)
+    }
+}
+
+/// No pawns and no checks, the first king to reach the eighth rank wins.
+/// If white gets there first, black still has one move to draw by getting
+/// there too.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct RacingKings;
+
+impl Variant for RacingKings {
+    fn name(&self) -> &str {
+        "Racing Kings"
+    }
+
+    fn start_fen(&self) -> &str {
+        "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1"
+    }
+
+    /// Neither king may be attacked after the move.
+    fn is_legal(&self, game: &Game, _move: &Move) -> bool {
+        if game.leaves_king_in_check(_move) {
+            return false;
+        }
+        let mut after = game.clone();
+        after.gameboard = game.board_after(_move);
+        after.turn = opposite(game.turn);
+        !after.is_check()
+    }
+
+    fn outcome(&self, game: &Game) -> Option<Outcome> {
+        let on_last_row = |color| matches!(find_king(&game.gameboard, color), Some(king) if king / 8 == 0);
+        match (on_last_row(PieceColor::White), on_last_row(PieceColor::Black)) {
+            (true, true) => return Some(Outcome::draw("both kings reached the eighth rank")),
+            (false, true) => return Some(Outcome::win(PieceColor::Black, "king reached the eighth rank")),
+            (true, false) => {
+                let black_can_follow = game.turn == PieceColor::Black
+                    && game.get_legal_moves().iter().any(|_move| {
+                        matches!(&game.gameboard[_move.from], Some(piece) if piece.piecetype == PieceType::King)
+                            && _move.to / 8 == 0
+                    });
+                if !black_can_follow {
+                    return Some(Outcome::win(PieceColor::White, "king reached the eighth rank"));
+                }
+            }
+            (false, false) => (),
+        }
+        if game.get_legal_moves().is_empty() {
+            return Some(Outcome::draw("stalemate"));
+        }
+        None
+    }
+}
+
+/// A capture explodes, removing the capturing piece and every piece but
+/// pawns next to the captured one. Blowing up the enemy king wins, so kings
+/// may not capture, and kings standing next to each other are never in check.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Atomic;
+
+impl Atomic {
+    /// The board after `_move`, including the explosion of a capture.
+    fn board_after(game: &Game, _move: &Move) -> Vec<Option<Piece>> {
+        let mut board = game.board_after(_move);
+        if is_capture(game, _move) {
+            explode(&mut board, _move.to);
+        }
+        board
+    }
+}
+
+impl Variant for Atomic {
+    fn name(&self) -> &str {
+        "Atomic"
+    }
+
+    fn is_legal(&self, game: &Game, _move: &Move) -> bool {
+        let color = match &game.gameboard[_move.from] {
+            Some(piece) if piece.piecetype == PieceType::King && is_capture(game, _move) => return false,
+            Some(piece) => piece.piececolor,
+            None => return false,
+        };
+        let board = Atomic::board_after(game, _move);
+        if find_king(&board, color).is_none() {
+            return false;
+        }
+        if find_king(&board, opposite(color)).is_none() {
+            return true;
+        }
+        !self.king_attacked(game, &board, color)
+    }
+
+    fn king_attacked(&self, game: &Game, board: &[Option<Piece>], color: PieceColor) -> bool {
+        let (king, enemy_king) = match (find_king(board, color), find_king(board, opposite(color))) {
+            (Some(king), Some(enemy_king)) => (king, enemy_king),
+            _ => return false,
+        };
+        !neighbours(king).contains(&enemy_king) && game.square_attacked(board, king, opposite(color))
+    }
+
+    fn after_move(&self, game: &mut Game, _move: &Move, captured: Option<Piece>) {
+        if captured.is_none() {
+            return;
+        }
+        explode(&mut game.gameboard, _move.to);
+        let board = &game.gameboard;
+        game.castling.retain(|rook| match &board[*rook] {
+            Some(piece) => find_king(board, piece.piececolor).is_some_and(|king| king / 8 == rook / 8),
+            None => false,
+        });
+    }
+
+    fn outcome(&self, game: &Game) -> Option<Outcome> {
+        if find_king(&game.gameboard, game.turn).is_none() {
+            return Some(Outcome::win(opposite(game.turn), "explosion"));
+        }
+        Standard.outcome(game)
+    }
+}
+
+/// Captured pieces change color and go to the capturer's pocket, and instead
+/// of moving a player may drop a piece from the pocket on an empty square.
+/// Promoted pieces turn back into pawns when captured.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Crazyhouse;
+
+impl Variant for Crazyhouse {
+    fn name(&self) -> &str {
+        "Crazyhouse"
+    }
+
+    fn start_fen(&self) -> &str {
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1"
+    }
+
+    fn after_move(&self, game: &mut Game, _move: &Move, captured: Option<Piece>) {
+        if let Some(piece) = captured {
+            let piecetype = if piece.promoted { PieceType::Pawn } else { piece.piecetype };
+            game.add_to_pocket(game.turn, piecetype);
+        }
+    }
+
+    fn uses_pockets(&self) -> bool {
+        true
+    }
+}
+
+/// Losing chess: capturing is compulsory, the king is an ordinary piece that
+/// can be captured and promoted to, and there is no check or castling. Losing
+/// all pieces or being stalemated wins.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Antichess;
+
+impl Variant for Antichess {
+    fn name(&self) -> &str {
+        "Antichess"
+    }
+
+    fn start_fen(&self) -> &str {
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1"
+    }
+
+    fn is_legal(&self, _game: &Game, _move: &Move) -> bool {
+        true
+    }
+
+    fn king_attacked(&self, _game: &Game, _board: &[Option<Piece>], _color: PieceColor) -> bool {
+        false
+    }
+
+    fn filter_moves(&self, game: &Game, moves: &mut Vec<Move>) {
+        if moves.iter().any(|_move| is_capture(game, _move)) {
+            moves.retain(|_move| is_capture(game, _move));
+        }
+    }
+
+    fn outcome(&self, game: &Game) -> Option<Outcome> {
+        if !game.get_legal_moves().is_empty() {
+            return None;
+        }
+        let has_pieces = game.gameboard.iter().flatten().any(|piece| piece.piececolor == game.turn);
+        if has_pieces {
+            Some(Outcome::win(game.turn, "stalemate"))
+        } else {
+            Some(Outcome::win(game.turn, "no pieces left"))
+        }
+    }
+
+    fn promotion_pieces(&self) -> &[PieceType] {
+        &[PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight, PieceType::King]
+    }
+
+    fn kings_required(&self) -> bool {
+        false
+    }
+}
+
+/// White has 36 pawns and no king against the normal black army. Black wins
+/// by capturing every white piece, white by checkmate.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Horde;
+
+impl Variant for Horde {
+    fn name(&self) -> &str {
+        "Horde"
+    }
+
+    fn start_fen(&self) -> &str {
+        "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1"
+    }
+
+    fn outcome(&self, game: &Game) -> Option<Outcome> {
+        let white_left = game.gameboard.iter().flatten().any(|piece| piece.piececolor == PieceColor::White);
+        if !white_left {
+            return Some(Outcome::win(PieceColor::Black, "all pieces captured"));
+        }
+        Standard.outcome(game)
+    }
+
+    fn kings_required(&self) -> bool {
+        false
+    }
+
+    fn pawns_on_first_rank(&self) -> bool {
+        true
+    }
+}
+
+/// Tells if `_move` takes an enemy piece.
+pub(crate) fn is_capture(game: &Game, _move: &Move) -> bool {
+    if game.en_passant_capture(_move).is_some() {
+        return true;
+    }
+    match (&game.gameboard[_move.from], &game.gameboard[_move.to]) {
+        (Some(piece), Some(target)) => piece.piececolor != target.piececolor,
+        _ => false,
+    }
+}
+
+/// Removes the piece on `pos` and every piece but pawns around it.
+fn explode(board: &mut [Option<Piece>], pos: usize) {
+    board[pos] = None;
+    for square in neighbours(pos) {
+        if matches!(&board[square], Some(piece) if piece.piecetype != PieceType::Pawn) {
+            board[square] = None;
+        }
+    }
+}
+
+/// The up to eight squares next to `pos`.
+fn neighbours(pos: usize) -> Vec<usize> {
+    let (row, col) = ((pos / 8) as i16, (pos % 8) as i16);
+    let mut squares: Vec<usize> = Vec::new();
+    for up in -1..=1 {
+        for right in -1..=1 {
+            let (r, c) = (row + up, col + right);
+            if (up, right) != (0, 0) && (0..8).contains(&r) && (0..8).contains(&c) {
+                squares.push((r * 8 + c) as usize);
+            }
+        }
+    }
+    squares
+}
+
+/// The variant built into the crate with the given name, as written in the
+/// PGN "Variant" tag. Case, spaces and hyphens do not matter.
+pub fn from_name(name: &str) -> Option<Box<dyn Variant>> {
+    let name: String = name
+        .chars()
+        .filter(|c| *c != ' ' && *c != '-')
+        .collect::<String>()
+        .to_lowercase();
+    match name.as_str() {
+        "standard" | "chess" => Some(Box::new(Standard)),
+        "kingofthehill" => Some(Box::new(KingOfTheHill)),
+        "threecheck" => Some(Box::new(ThreeCheck)),
+        "racingkings" => Some(Box::new(RacingKings)),
+        "atomic" => Some(Box::new(Atomic)),
+        "crazyhouse" => Some(Box::new(Crazyhouse)),
+        "antichess" | "losingchess" | "giveaway" => Some(Box::new(Antichess)),
+        "horde" => Some(Box::new(Horde)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GameState;
+
+    /// Kings may not leave their first two ranks.
+    struct Homebound;
+
+    impl Variant for Homebound {
+        fn name(&self) -> &str {
+            "Homebound"
+        }
+
+        fn start_fen(&self) -> &str {
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1"
+        }
+
+        fn filter_moves(&self, game: &Game, moves: &mut Vec<Move>) {
+            moves.retain(|_move| match &game.gameboard[_move.from] {
+                Some(piece) if piece.piecetype == PieceType::King => match piece.piececolor {
+                    PieceColor::White => _move.to / 8 >= 6,
+                    PieceColor::Black => _move.to / 8 <= 1,
+                },
+                _ => true,
+            });
+        }
+
+        fn promotion_pieces(&self) -> &[PieceType] {
+            &[PieceType::Knight]
+        }
+    }
+
+    #[test]
+    fn house_variant() {
+        let mut game = Game::new_variant(Homebound);
+        assert_eq!(game.variant().name(), "Homebound");
+        assert_eq!(game.get_possible_moves("e1").unwrap(), vec!["e2", "f2", "f1", "d1", "d2"]);
+        game.make_move("e1", "e2");
+        game.make_move("e8", "e7");
+        assert_eq!(game.get_possible_moves("e2").unwrap(), vec!["f2", "f1", "e1", "d1", "d2"]);
+
+        // the variant is kept when moves are taken back
+        game.undo();
+        assert_eq!(game.variant().name(), "Homebound");
+
+        let mut game = Game::from_fen_variant("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", Homebound).unwrap();
+        game.make_move("b7", "b8");
+        game.set_promotion("q");
+        assert_eq!(game.get_game_state(), GameState::SetPromotion);
+        game.set_promotion("kn");
+        assert_eq!(game.to_fen(), "1N2k3/8/8/8/8/8/8/4K3 b - - 0 1");
+    }
+
+    #[test]
+    fn checkmate_and_stalemate() {
+        let mut game = Game::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        assert_eq!(game.get_outcome(), None);
+        game.make_move("a1", "a8");
+        assert_eq!(game.get_game_state(), GameState::GameOver);
+        assert_eq!(game.get_outcome(), Some(Outcome::win(PieceColor::White, "checkmate")));
+        assert!(game.get_legal_moves().is_empty());
+
+        let mut game = Game::from_fen("k7/8/2Q5/8/8/8/8/6K1 w - - 0 1").unwrap();
+        game.make_move("c6", "b6");
+        assert_eq!(game.get_outcome(), Some(Outcome::draw("stalemate")));
+    }
+
+    #[test]
+    fn moves_out_of_check_only() {
+        // the rook on e1 checks, so only the king and the blocking bishop may move
+        let game = Game::from_fen("4k3/8/8/8/8/8/3b4/4R1K1 b - - 0 1").unwrap();
+        assert_eq!(game.get_game_state(), GameState::Check);
+        let moves: Vec<String> = game.get_legal_moves().iter().map(|_move| _move.to_string()).collect();
+        assert_eq!(moves, vec!["e8f8", "e8f7", "e8d7", "e8d8", "d2e3", "d2e1"]);
+    }
+
+    #[test]
+    fn king_of_the_hill() {
+        let mut game = Game::from_fen_variant("4k3/8/8/8/8/3K4/8/8 w - - 0 1", KingOfTheHill).unwrap();
+        game.make_move("d3", "d4");
+        assert_eq!(game.get_game_state(), GameState::GameOver);
+        assert_eq!(game.get_outcome(), Some(Outcome::win(PieceColor::White, "king of the hill")));
+        assert!(game.to_pgn().contains("[Variant \"King of the Hill\"]"));
+        assert!(game.to_pgn().ends_with("1. Kd4 1-0\n"));
+    }
+
+    #[test]
+    fn three_check() {
+        let mut game = Game::from_fen_variant("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", ThreeCheck).unwrap();
+        assert_eq!(game.to_fen(), "4k3/8/8/8/8/8/8/R3K3 w - - 3+3 0 1");
+        game.make_move("a1", "a8");
+        assert_eq!(game.get_checks(PieceColor::White), 1);
+        assert_eq!(game.to_fen(), "R3k3/8/8/8/8/8/8/4K3 b - - 2+3 1 1");
+        for (from, to) in [("e8", "e7"), ("a8", "a7"), ("e7", "e6")] {
+            game.make_move(from, to);
+        }
+        let loaded = Game::from_fen_variant(&game.to_fen(), ThreeCheck).unwrap();
+        assert_eq!(loaded.get_checks(PieceColor::White), 2);
+
+        game.make_move("a7", "a6");
+        assert_eq!(game.get_outcome(), Some(Outcome::win(PieceColor::White, "three checks")));
+        assert!(Game::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 3+3 0 1").is_none());
+    }
+
+    #[test]
+    fn racing_kings() {
+        let game = Game::new_variant(RacingKings);
+        assert_eq!(game.get_legal_moves().len(), 21);
+
+        // black can still reach the eighth rank, so the game goes on
+        let mut game = Game::from_fen_variant("8/1K4k1/8/8/8/8/8/8 w - - 0 1", RacingKings).unwrap();
+        game.make_move("b7", "b8");
+        assert_eq!(game.get_outcome(), None);
+        game.make_move("g7", "g8");
+        assert_eq!(game.get_outcome(), Some(Outcome::draw("both kings reached the eighth rank")));
+
+        let mut game = Game::from_fen_variant("8/1K6/8/8/8/8/6k1/8 w - - 0 1", RacingKings).unwrap();
+        game.make_move("b7", "b8");
+        assert_eq!(game.get_outcome(), Some(Outcome::win(PieceColor::White, "king reached the eighth rank")));
+    }
+
+    #[test]
+    fn atomic_explosions() {
+        // the bishop next to e5 goes up with the knight, the pawn on f6 stays
+        let mut game = Game::from_fen_variant("4k3/8/5p2/3bp3/8/5N2/8/4K3 w - - 0 1", Atomic).unwrap();
+        game.make_move("f3", "e5");
+        assert_eq!(game.to_fen(), "4k3/8/5p2/8/8/8/8/4K3 b - - 0 1");
+
+        let mut game = Game::from_fen_variant("4k3/4p3/8/8/8/8/8/4R1K1 w - - 0 1", Atomic).unwrap();
+        game.make_move("e1", "e7");
+        assert_eq!(game.get_game_state(), GameState::GameOver);
+        assert_eq!(game.get_outcome(), Some(Outcome::win(PieceColor::White, "explosion")));
+        assert!(game.to_pgn().ends_with("1. Rxe7 1-0\n"));
+    }
+
+    #[test]
+    fn atomic_move_rules() {
+        // kings can not capture, and the own king may not be blown up
+        let game = Game::from_fen_variant("4k3/8/8/8/8/8/3pp3/3RK3 w - - 0 1", Atomic).unwrap();
+        assert!(!game.get_possible_moves("e1").unwrap().contains(&"e2".to_string()));
+        assert!(!game.get_possible_moves("d1").unwrap().contains(&"d2".to_string()));
+
+        // kings next to each other are not in check
+        let game = Game::from_fen_variant("8/8/8/8/8/8/R3k3/4K3 b - - 0 1", Atomic).unwrap();
+        assert_eq!(game.get_game_state(), GameState::InProgress);
+        assert!(Game::from_fen("8/8/8/8/8/8/R3k3/4K3 b - - 0 1").unwrap().is_check());
+    }
+
+    #[test]
+    fn crazyhouse_drops() {
+        let mut game = Game::new_variant(Crazyhouse);
+        for (from, to) in [("e2", "e4"), ("d7", "d5"), ("e4", "d5"), ("d8", "d5")] {
+            game.make_move(from, to);
+        }
+        assert_eq!(game.get_pocket(PieceColor::White), &[PieceType::Pawn]);
+        assert_eq!(game.to_fen(), "rnb1kbnr/ppp1pppp/8/3q4/8/8/PPPP1PPP/RNBQKBNR[Pp] w KQkq - 0 3");
+        let drops: Vec<Move> = game.get_legal_moves().into_iter().filter(|_move| _move.drop.is_some()).collect();
+        assert_eq!(drops.len(), 33);
+
+        assert_eq!(game.make_san_move("P@e4"), Some(GameState::InProgress));
+        assert_eq!(game.get_history().last().unwrap().to_string(), "P@e4");
+        assert!(game.get_pocket(PieceColor::White).is_empty());
+        assert_eq!(game.make_drop(PieceType::Pawn, "d1"), Some(GameState::InProgress));
+        assert_eq!(game.get_history().len(), 5);
+
+        let pgn = game.to_pgn();
+        assert!(pgn.ends_with("3. P@e4 *\n"));
+        assert_eq!(Game::from_pgn(&pgn).unwrap().to_fen(), game.to_fen());
+    }
+
+    #[test]
+    fn crazyhouse_promoted_piece_reverts() {
+        let mut game = Game::from_fen_variant("rk6/1P6/8/8/8/8/8/4K3[] w - - 0 1", Crazyhouse).unwrap();
+        game.make_move("b7", "a8");
+        game.set_promotion("q");
+        assert_eq!(game.to_fen(), "Q~k6/8/8/8/8/8/8/4K3[R] b - - 0 1");
+        game.make_move("b8", "a8");
+        assert_eq!(game.to_fen(), "k7/8/8/8/8/8/8/4K3[Rp] w - - 0 2");
+        assert!(Game::from_fen("k7/8/8/8/8/8/8/4K3[Rp] w - - 0 2").is_none());
+    }
+
+    #[test]
+    fn antichess() {
+        let mut game = Game::new_variant(Antichess);
+        game.make_move("e2", "e3");
+        game.make_move("b7", "b5");
+        assert_eq!(game.get_legal_moves(), vec![Move::from_uci("f1b5").unwrap()]);
+
+        // no check, the king may walk into the rook
+        let game = Game::from_fen_variant("4k3/8/8/8/8/8/8/4R1K1 b - - 0 1", Antichess).unwrap();
+        assert_eq!(game.get_game_state(), GameState::InProgress);
+        assert!(game.get_possible_moves("e8").unwrap().contains(&"e7".to_string()));
+
+        // taking the king and promoting to a king
+        let mut game = Game::from_fen_variant("k7/1P6/8/8/8/8/8/8 w - - 0 1", Antichess).unwrap();
+        assert_eq!(game.get_legal_moves(), vec![Move::from_uci("b7a8").unwrap()]);
+        game.make_san_move("bxa8=K").unwrap();
+        assert_eq!(game.to_fen(), "K7/8/8/8/8/8/8/8 b - - 0 1");
+        assert_eq!(game.get_outcome(), Some(Outcome::win(PieceColor::Black, "no pieces left")));
+
+        let game = Game::from_fen_variant("8/8/8/8/8/p7/P7/8 b - - 0 1", Antichess).unwrap();
+        assert_eq!(game.get_outcome(), Some(Outcome::win(PieceColor::Black, "stalemate")));
+    }
+
+    #[test]
+    fn horde() {
+        let game = Game::new_variant(Horde);
+        assert_eq!(game.get_legal_moves().len(), 8);
+        assert!(!game.is_check());
+        assert_eq!(game.to_fen(), Horde.start_fen());
+
+        // pawns on the first rank move like from the second, without jumping
+        let game = Game::from_fen_variant("4k3/8/8/8/8/8/8/P7 w - - 0 1", Horde).unwrap();
+        assert_eq!(game.get_possible_moves("a1").unwrap(), vec!["a2", "a3"]);
+        let game = Game::from_fen_variant("4k3/8/8/8/8/8/n7/P6P w - - 0 1", Horde).unwrap();
+        assert!(game.get_possible_moves("a1").unwrap().is_empty());
+        assert!(Game::from_fen("4k3/8/8/8/8/8/8/P7 w - - 0 1").is_none());
+
+        let mut game = Game::from_fen_variant("4k3/8/8/8/8/8/8/Pr6 b - - 0 1", Horde).unwrap();
+        game.make_move("b1", "a1");
+        assert_eq!(game.get_outcome(), Some(Outcome::win(PieceColor::Black, "all pieces captured")));
+    }
+}
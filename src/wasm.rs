@@ -0,0 +1,107 @@
+//! WebAssembly bindings, enabled with the `wasm` feature.
+//!
+//! Exposes `Game` to JavaScript with camelCase methods. Squares are strings
+//! like "e2" and moves use coordinate notation like "e2e4".
+//!
+//! ```js
+//! const game = new Game();
+//! game.legalMoves("e2");        // ["e3", "e4"]
+//! game.makeMove("e2", "e4");    // GameState.InProgress
+//! game.undo();
+//! ```
+
+use wasm_bindgen::prelude::*;
+
+use crate::{Game, GameState, PieceColor};
+
+#[wasm_bindgen(js_name = Game)]
+pub struct WasmGame {
+    game: Game,
+}
+
+#[wasm_bindgen(js_class = Game)]
+impl WasmGame {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmGame {
+        WasmGame { game: Game::new() }
+    }
+
+    #[wasm_bindgen(js_name = fromFen)]
+    pub fn from_fen(fen: &str) -> Result<WasmGame, JsError> {
+        match Game::from_fen(fen) {
+            Some(game) => Ok(WasmGame { game }),
+            None => Err(JsError::new(&format!("invalid FEN \"{}\"", fen))),
+        }
+    }
+
+    #[wasm_bindgen(js_name = toFen)]
+    pub fn to_fen(&self) -> String {
+        self.game.to_fen()
+    }
+
+    /// Squares the piece on `square` can move to, empty if there is no piece
+    /// of the side to move there.
+    #[wasm_bindgen(js_name = legalMoves)]
+    pub fn legal_moves(&self, square: &str) -> Vec<String> {
+        self.game.get_possible_moves(square).unwrap_or_default()
+    }
+
+    /// Every move of the side to move, e.g. ["a2a3", "a2a4", ...].
+    #[wasm_bindgen(js_name = allLegalMoves)]
+    pub fn all_legal_moves(&self) -> Vec<String> {
+        self.game
+            .get_legal_moves()
+            .iter()
+            .map(|_move| _move.to_string())
+            .collect()
+    }
+
+    /// Throws if the move is not allowed.
+    #[wasm_bindgen(js_name = makeMove)]
+    pub fn make_move(&mut self, from: &str, to: &str) -> Result<GameState, JsError> {
+        let played = self.game.get_history().len();
+        let state = self.game.make_move(from, to);
+        match state {
+            Some(state) if self.game.get_history().len() > played => Ok(state),
+            _ => Err(JsError::new(&format!("illegal move {}{}", from, to))),
+        }
+    }
+
+    /// Chooses the piece for a pending promotion: "q", "r", "b" or "kn".
+    #[wasm_bindgen(js_name = setPromotion)]
+    pub fn set_promotion(&mut self, piece: &str) -> GameState {
+        self.game.set_promotion(piece);
+        self.game.get_game_state()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn state(&self) -> GameState {
+        self.game.get_game_state()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn turn(&self) -> PieceColor {
+        self.game.turn
+    }
+
+    /// Moves made so far in coordinate notation.
+    #[wasm_bindgen(getter)]
+    pub fn history(&self) -> Vec<String> {
+        self.game
+            .get_history()
+            .iter()
+            .map(|_move| _move.to_string())
+            .collect()
+    }
+
+    /// Takes back the last move, returns false if there was none.
+    pub fn undo(&mut self) -> bool {
+        self.game.undo().is_some()
+    }
+}
+
+impl Default for WasmGame {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,35 @@
+//! Run in node with `cargo test --target wasm32-unknown-unknown --features wasm`
+//! (needs `wasm-bindgen-cli` for the test runner, see .cargo/config.toml).
+#![cfg(all(feature = "wasm", target_arch = "wasm32"))]
+
+use erikrub_chess_lib::wasm::WasmGame;
+use erikrub_chess_lib::{GameState, PieceColor};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[wasm_bindgen_test]
+fn play_and_undo() {
+    let mut game = WasmGame::new();
+    assert_eq!(game.legal_moves("e2"), vec!["e3", "e4"]);
+    assert_eq!(game.make_move("e2", "e4").ok(), Some(GameState::InProgress));
+    assert_eq!(game.turn(), PieceColor::Black);
+    assert_eq!(game.history(), vec!["e2e4"]);
+
+    assert!(game.undo());
+    assert_eq!(game.turn(), PieceColor::White);
+    assert!(!game.undo());
+}
+
+#[wasm_bindgen_test]
+fn illegal_move_throws() {
+    let mut game = WasmGame::new();
+    assert!(game.make_move("e2", "e5").is_err());
+    assert!(game.make_move("x9", "e5").is_err());
+}
+
+#[wasm_bindgen_test]
+fn promotion() {
+    let mut game = WasmGame::from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").ok().unwrap();
+    assert_eq!(game.make_move("b7", "b8").ok(), Some(GameState::SetPromotion));
+    assert_eq!(game.set_promotion("q"), GameState::InProgress);
+    assert_eq!(game.to_fen(), "1Q2k3/8/8/8/8/8/8/4K3 b - - 0 1");
+}